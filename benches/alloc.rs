@@ -0,0 +1,117 @@
+#![allow(clippy::needless_return)]
+
+use file_parser::parser_v2::{parse_str, ParseOptions};
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Аллокатор, который считает количество и общий размер выделений.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+
+        // SAFETY: требования к `layout` те же, что и у вызывающей стороны.
+        return unsafe { System.alloc(layout) };
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` выделен этим же аллокатором через `System`.
+        unsafe { System.dealloc(ptr, layout) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+
+        // SAFETY: `ptr` выделен этим же аллокатором через `System`.
+        return unsafe { System.realloc(ptr, layout, new_size) };
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Создает файл из `fields` полей по `texts` строк, у каждого поля свой тэг
+/// поверх общего для всех.
+fn many_fields_input(fields: usize, texts: usize) -> String {
+    let mut input = String::from("@lang de ru\n#common\n");
+
+    for field in 0..fields {
+        input.push_str(&format!("#field{field}\n"));
+
+        for index in 0..texts {
+            input.push_str(&format!("Satz {index} -- Предложение {index}\n"));
+        }
+
+        input.push_str(&format!("##field{field}\n"));
+    }
+
+    return input;
+}
+
+/// Создает файл из `lines` строк с текстом без директив.
+fn plain_input(lines: usize) -> String {
+    let mut input = String::new();
+
+    for index in 0..lines {
+        input.push_str(&format!("Satz {index} -- Предложение {index}\n"));
+    }
+
+    return input;
+}
+
+/// Создает файл из `lines` строк, где в каждой второй строке есть экранированный
+/// разделитель и комментарий в конце.
+fn escaped_input(lines: usize) -> String {
+    let mut input = String::new();
+
+    for index in 0..lines {
+        match index % 2 {
+            0 => input.push_str(&format!(
+                "Satz \\-- {index} -- Предложение {index} // Notiz\n"
+            )),
+            _ => input.push_str(&format!("Satz {index} -- Предложение {index}\n")),
+        }
+    }
+
+    return input;
+}
+
+/// Выводит количество и общий размер выделений памяти при парсинге
+/// (`cargo bench --bench alloc`). Criterion измеряет только время, поэтому
+/// выделения считаются собственным глобальным аллокатором.
+///
+/// Перенос текстов полей в `update_response` без копирования сократил количество
+/// выделений: `many_fields` - с 755453 до 715452, `plain` - с 483837 до 443836,
+/// `escaped` - с 383863 до 363862 (замерено до и после этого изменения, дальше числа
+/// меняются вместе с парсером).
+fn main() {
+    let options = ParseOptions::default();
+
+    for (name, input) in [
+        ("many_fields", many_fields_input(10_000, 2)),
+        ("plain", plain_input(20_000)),
+        ("escaped", escaped_input(20_000)),
+    ] {
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes = BYTES.load(Ordering::Relaxed);
+
+        let response = parse_str(&input, &options).unwrap();
+
+        println!(
+            "{name}: {} выделений, {} байт",
+            ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+            BYTES.load(Ordering::Relaxed) - bytes,
+        );
+
+        drop(response);
+    }
+}
@@ -0,0 +1,53 @@
+#![allow(clippy::needless_return)]
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use file_parser::parser_v2::{parse_str, ParseOptions};
+
+/// Создает файл из `lines` строк с текстом, где каждые 50 строк открывается новый тэг.
+fn tagged_input(lines: usize) -> String {
+    let mut input = String::from("@sep =\n@lang de ru\n");
+
+    for index in 0..lines {
+        if index % 50 == 0 {
+            input.push_str(&format!("#block{}\n", index / 50));
+        }
+
+        input.push_str(&format!("Satz {index} = Предложение {index}\n"));
+    }
+
+    return input;
+}
+
+/// Создает файл из `lines` строк с текстом без директив.
+fn plain_input(lines: usize) -> String {
+    let mut input = String::new();
+
+    for index in 0..lines {
+        input.push_str(&format!("Satz {index} -- Предложение {index}\n"));
+    }
+
+    return input;
+}
+
+fn parse_benchmark(c: &mut Criterion) {
+    let options = ParseOptions::default();
+    let mut group = c.benchmark_group("parse_str");
+    group.sample_size(10);
+
+    for (name, lines) in [("small", 100), ("medium", 10_000), ("large", 200_000)] {
+        for (kind, input) in [
+            ("plain", plain_input(lines)),
+            ("tagged", tagged_input(lines)),
+        ] {
+            group.throughput(Throughput::Bytes(input.len() as u64));
+            group.bench_with_input(BenchmarkId::new(kind, name), &input, |b, input| {
+                b.iter(|| parse_str(black_box(input), &options).unwrap());
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,133 @@
+use crate::parser_v2::{ParseError, ParseOptionsBuilder};
+
+use serde::Deserialize;
+
+use std::{fs, io, path::Path};
+
+/// Имя файла настроек, который ищется в рабочем каталоге.
+pub const CONFIG_FILE: &str = "fileparser.json";
+
+/// Структура, описывающая файл настроек `fileparser.json`.
+///
+/// Все ключи необязательны:
+///
+/// * `default_separator` - разделитель, если в файле нет директивы `@sep`.
+/// * `original_language`, `translate_language` - языки, если в файле нет директивы `@lang`.
+/// * `forbidden_chars` - символы, запрещенные в тексте.
+///
+/// Настройки из файла имеют наименьший приоритет после встроенных значений:
+/// их переопределяют флаги командной строки, а те, в свою очередь, директивы в файле.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub default_separator: Option<String>,
+    pub original_language: Option<String>,
+    pub translate_language: Option<String>,
+    pub forbidden_chars: Option<String>,
+}
+
+impl Config {
+    /// Читает файл настроек в формате JSON.
+    pub fn load(path: &Path) -> Result<Config, ParseError> {
+        let text = fs::read_to_string(path)?;
+
+        return serde_json::from_str(&text)
+            .map_err(|err| ParseError::InvalidConfig(err.to_string()));
+    }
+
+    /// Ищет [`CONFIG_FILE`] в каталоге `dir` и читает его.
+    ///
+    /// Если файла нет, возвращает `Ok(None)`.
+    pub fn discover(dir: &Path) -> Result<Option<Config>, ParseError> {
+        return match Config::load(&dir.join(CONFIG_FILE)) {
+            Ok(config) => Ok(Some(config)),
+            Err(ParseError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        };
+    }
+
+    /// Переносит заданные в файле значения в построитель настроек парсинга.
+    pub fn apply(&self, mut builder: ParseOptionsBuilder) -> ParseOptionsBuilder {
+        if let Some(separator) = &self.default_separator {
+            builder = builder.separator(separator.as_str());
+        }
+
+        if let Some(language) = &self.original_language {
+            builder = builder.original_language(language.as_str());
+        }
+
+        if let Some(language) = &self.translate_language {
+            builder = builder.translate_language(language.as_str());
+        }
+
+        if let Some(chars) = &self.forbidden_chars {
+            builder = builder.forbidden_chars(chars.as_str());
+        }
+
+        return builder;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::parser_v2::{parse_str, ParseOptions};
+
+    /// Создает временный каталог с файлом настроек `text`.
+    fn dir_with_config(text: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), text).unwrap();
+
+        return dir;
+    }
+
+    #[test]
+    fn config_separator_is_used_without_directive() {
+        let dir = dir_with_config(r#"{ "default_separator": "=", "original_language": "en" }"#);
+        let config = Config::discover(dir.path()).unwrap().unwrap();
+        let options = config.apply(ParseOptions::builder()).build();
+
+        let response = parse_str("Eins = Один\n", &options).unwrap();
+
+        assert_eq!(response.fields()[0].content()[0].translate, "Один");
+        assert_eq!(response.languages.original, "en");
+
+        let response = parse_str("@sep :\nEins : Один = eins\n", &options).unwrap();
+
+        assert_eq!(response.fields()[0].content()[0].translate, "Один = eins");
+    }
+
+    #[test]
+    fn later_builder_calls_override_config() {
+        let dir = dir_with_config(r#"{ "forbidden_chars": "!", "original_language": "en" }"#);
+        let config = Config::discover(dir.path()).unwrap().unwrap();
+        let options = config
+            .apply(ParseOptions::builder())
+            .original_language("de")
+            .build();
+
+        let response = parse_str("Eins! -- Один\nZwei <b> -- Два\n", &options).unwrap();
+
+        assert_eq!(response.errors().len(), 1);
+        assert_eq!(response.errors()[0].location().line, 1);
+        assert_eq!(response.languages.original, "de");
+    }
+
+    #[test]
+    fn missing_config_is_not_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(Config::discover(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_key_is_rejected() {
+        let dir = dir_with_config(r#"{ "separator": "=" }"#);
+
+        assert!(matches!(
+            Config::discover(dir.path()),
+            Err(ParseError::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,779 @@
+use crate::parser_v2::{Languages, Response, Text, DEFAULT_SEPARATOR};
+
+use serde_json::{json, Map, Value};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+#[cfg(any(feature = "parquet", feature = "yaml"))]
+use crate::parser_v2::ParseError;
+
+/// Записывает результат парсинга в формате JSON прямо в `writer`,
+/// не собирая промежуточную строку целиком в памяти.
+///
+/// При `pretty` вывод форматируется с отступами.
+pub fn write_json<W: Write>(response: &Response, writer: W, pretty: bool) -> io::Result<()> {
+    match pretty {
+        true => serde_json::to_writer_pretty(writer, response)?,
+        false => serde_json::to_writer(writer, response)?,
+    }
+
+    return Ok(());
+}
+
+/// Ключ [`to_nested_json`], под которым лежат тексты самого узла.
+pub const NESTED_ROOT_KEY: &str = "_root";
+
+/// Узел дерева тэгов для [`to_nested_json`]: тексты полей, чьи тэги заканчиваются
+/// на этом узле, и дочерние узлы по следующему тэгу.
+#[derive(Default)]
+struct TagNode<'a> {
+    texts: Vec<&'a Text>,
+    children: BTreeMap<&'a str, TagNode<'a>>,
+}
+
+impl TagNode<'_> {
+    /// Преобразует узел в JSON. Узел без дочерних узлов (кроме корня) становится
+    /// массивом текстов, остальные - объектами, где тексты лежат под [`NESTED_ROOT_KEY`].
+    fn into_value(self, root: bool) -> Value {
+        let texts: Vec<Value> = self
+            .texts
+            .iter()
+            .map(|text| json!({ "original": text.original, "translate": text.translate }))
+            .collect();
+
+        if self.children.is_empty() && !root {
+            return Value::Array(texts);
+        }
+
+        let mut object = Map::new();
+
+        for (tag, child) in self.children {
+            object.insert(tag.to_string(), child.into_value(false));
+        }
+
+        if !texts.is_empty() {
+            object.insert(NESTED_ROOT_KEY.to_string(), Value::Array(texts));
+        }
+
+        return Value::Object(object);
+    }
+}
+
+/// Преобразует результат парсинга в дерево JSON, сгруппированное по тэгам.
+///
+/// Тэги каждого поля сортируются по алфавиту и образуют путь в дереве:
+/// тексты поля с тэгами `b, a` попадают в `{ "a": { "b": [...] } }`. Каждый текст
+/// записывается объектом `{ "original", "translate" }`. Тексты полей без тэгов,
+/// а также тексты узла, у которого есть и дочерние тэги (например, поля `a`
+/// рядом с полем `a, b`), лежат под ключом [`NESTED_ROOT_KEY`] (`_root`) этого узла.
+pub fn to_nested_json(response: &Response) -> Value {
+    let mut root = TagNode::default();
+
+    for field in response.fields.iter() {
+        let mut tags: Vec<&str> = field.tags.iter().map(|x| x.as_str()).collect();
+        tags.sort();
+
+        let mut node = &mut root;
+
+        for tag in tags {
+            node = node.children.entry(tag).or_default();
+        }
+
+        node.texts.extend(field.content.iter());
+    }
+
+    return root.into_value(true);
+}
+
+/// Экранирует специальные символы HTML (`&`, `<`, `>`, `"`, `'`).
+fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(ch),
+        }
+    }
+
+    return result;
+}
+
+/// Формирует самостоятельную HTML-страницу с таблицей для вычитки перевода.
+///
+/// Таблица содержит столбцы оригинала, перевода и тэгов. Содержимое всех ячеек
+/// экранируется, а строки без перевода отмечаются классом `untranslated` и подсвечиваются.
+pub fn to_html(response: &Response) -> String {
+    let mut rows = String::new();
+
+    for field in response.fields.iter() {
+        let mut tags: Vec<&str> = field.tags.iter().map(|x| x.as_str()).collect();
+        tags.sort();
+
+        let tags = escape_html(&tags.join(", "));
+
+        for text in field.content.iter() {
+            let class = match text.translate.is_empty() {
+                true => " class=\"untranslated\"",
+                false => "",
+            };
+
+            rows.push_str(&format!(
+                "<tr{class}><td>{}</td><td>{}</td><td>{tags}</td></tr>\n",
+                escape_html(&text.original),
+                escape_html(&text.translate),
+            ));
+        }
+    }
+
+    return format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>file-parser</title>
+<style>
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #999; padding: 4px 8px; }}
+tr.untranslated {{ background: #fdd; }}
+</style>
+</head>
+<body>
+<table>
+<tr><th>{}</th><th>{}</th><th>tags</th></tr>
+{rows}</table>
+</body>
+</html>
+",
+        escape_html(&response.languages.original),
+        escape_html(&response.languages.translate),
+    );
+}
+
+/// Экранирует значение ячейки CSV по RFC 4180: если в значении есть запятая,
+/// кавычка или перевод строки, оно заключается в кавычки, а кавычки внутри удваиваются.
+fn escape_csv(value: &str) -> String {
+    if !value.contains([',', '"', '\n', '\r']) {
+        return value.to_string();
+    }
+
+    return format!("\"{}\"", value.replace('"', "\"\""));
+}
+
+/// Преобразует результат парсинга в CSV со столбцами `tags,original,translate`.
+///
+/// Каждый текст становится отдельной строкой, тэги поля записываются через `;`
+/// в алфавитном порядке. Строки разделяются `\r\n`, как того требует RFC 4180.
+pub fn to_csv(response: &Response) -> String {
+    let mut csv = String::from("tags,original,translate\r\n");
+
+    for field in response.fields.iter() {
+        let mut tags: Vec<&str> = field.tags.iter().map(|x| x.as_str()).collect();
+        tags.sort();
+
+        let tags = escape_csv(&tags.join(";"));
+
+        for text in field.content.iter() {
+            csv.push_str(&format!(
+                "{tags},{},{}\r\n",
+                escape_csv(&text.original),
+                escape_csv(&text.translate),
+            ));
+        }
+    }
+
+    return csv;
+}
+
+/// Экранирует строку для файла `.strings`: обратную косую черту, кавычку
+/// и управляющие символы перевода строки, возврата каретки и табуляции.
+fn escape_strings(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for symbol in value.chars() {
+        match symbol {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(symbol),
+        }
+    }
+
+    return escaped;
+}
+
+/// Преобразует результат парсинга в файл локализации iOS (`.strings`).
+///
+/// Каждый текст записывается строкой `"ключ" = "перевод";`, перед которой
+/// в комментарии `/* ... */` стоит оригинал. Ключ состоит из тэгов поля
+/// в алфавитном порядке через `.` и номера текста среди всех текстов с теми же тэгами
+/// (с нуля), например `menu.title.0`; у поля без тэгов ключом служит только номер.
+/// Номера продолжаются между полями с одинаковыми тэгами (например, с разными `@lang`),
+/// а `.` и `\` внутри тэгов экранируются `\`, поэтому ключи не повторяются.
+pub fn to_ios_strings(response: &Response) -> String {
+    let mut strings = String::new();
+    let mut counters: HashMap<String, usize> = HashMap::new();
+
+    for field in response.fields.iter() {
+        let mut tags: Vec<&str> = field.tags.iter().map(|x| x.as_str()).collect();
+        tags.sort();
+
+        let prefix = tags
+            .iter()
+            .map(|tag| tag.replace('\\', "\\\\").replace('.', "\\."))
+            .collect::<Vec<String>>()
+            .join(".");
+        let counter = counters.entry(prefix.clone()).or_default();
+
+        for text in field.content.iter() {
+            let index = *counter;
+            *counter += 1;
+
+            let key = match prefix.is_empty() {
+                true => index.to_string(),
+                false => format!("{prefix}.{index}"),
+            };
+
+            if !strings.is_empty() {
+                strings.push('\n');
+            }
+
+            strings.push_str(&format!(
+                "/* {} */\n\"{}\" = \"{}\";\n",
+                text.original.replace("*/", "* /"),
+                escape_strings(&key),
+                escape_strings(&text.translate),
+            ));
+        }
+    }
+
+    return strings;
+}
+
+/// Экранирует разделитель, маркер комментария и начало переменной в тексте для [`to_txt`].
+fn escape_txt(text: &str, sep: &str, escaped_sep: &str) -> String {
+    return text
+        .replace(sep, escaped_sep)
+        .replace("//", "\\//")
+        .replace("${", "\\${");
+}
+
+/// Заключает текст в кавычки для [`to_txt`], удваивая кавычки внутри. Текст в кавычках
+/// парсер берет как есть, поэтому остальное экранирование не нужно.
+fn quote_txt(text: &str) -> String {
+    return format!("\"{}\"", text.replace('"', "\"\""));
+}
+
+/// Преобразует результат парсинга обратно в текстовый формат, который читает парсер.
+///
+/// В начало записываются комментарии из начала исходного файла
+/// (см. [`crate::parser_v2::ParseOptions::keep_header_comments`]), директивы `@lang` (если языки указаны) и `@sep`
+/// (если разделитель отличается от [`DEFAULT_SEPARATOR`]; разделитель с пробелами
+/// по краям и разделитель `auto` записываются в кавычках), затем каждое поле в блоке
+/// `@tags ... @@tags`, заметки поля записываются директивами `@comment`.
+/// Перед полем с собственными языками записывается директива `@lang` с этими языками,
+/// а перед следующим полем без них - снова языки файла.
+/// Разделитель, маркер комментария `//` и начало переменной `${` внутри текстов экранируются,
+/// а оригинал, начинающийся с `#`, записывается как `\#`. Оригинал, начинающийся с `"`
+/// или `@`, и перевод, начинающийся с `"`, записываются в кавычках, чтобы не читаться
+/// как текст в кавычках или директива.
+/// Из альтернативных переводов сохраняется только первый (`translate`).
+pub fn to_txt(response: &Response) -> String {
+    return to_txt_wrapped(response, 0);
+}
+
+/// Делает то же, что [`to_txt`], но переносит строки с текстами длиннее `width`
+/// символов по границам слов (см. [`wrap_line`]). `width`, равный 0, отключает перенос.
+/// Перенесенные строки склеиваются обратно только при чтении с
+/// [`crate::parser_v2::ParseOptions::line_continuation`].
+pub fn to_txt_wrapped(response: &Response, width: usize) -> String {
+    let mut txt = String::new();
+
+    let sep = match response.separator.is_empty() {
+        true => DEFAULT_SEPARATOR,
+        false => response.separator.as_str(),
+    };
+
+    for comment in response.header_comments.iter() {
+        txt.push_str(&format!("//{comment}\n"));
+    }
+
+    let languages = &response.languages;
+
+    if !languages.original.is_empty() && !languages.translate.is_empty() {
+        txt.push_str(&format!(
+            "@lang {} {}\n",
+            languages.original, languages.translate
+        ));
+    }
+
+    // Без кавычек разделитель `auto` читался бы как `@sep auto`.
+    if sep != DEFAULT_SEPARATOR && (sep != sep.trim() || sep == "auto") {
+        txt.push_str(&format!("@sep \"{sep}\"\n"));
+    } else if sep != DEFAULT_SEPARATOR {
+        txt.push_str(&format!("@sep {sep}\n"));
+    }
+
+    let escaped_sep = format!("\\{sep}");
+    let mut current_languages: Option<&Languages> = None;
+
+    for field in response.fields.iter() {
+        let mut tags: Vec<&str> = field.tags.iter().map(|x| x.as_str()).collect();
+        tags.sort();
+
+        let tags = tags.join(", ");
+
+        if !txt.is_empty() {
+            txt.push('\n');
+        }
+
+        if field.languages.as_ref() != current_languages {
+            let languages = field.languages.as_ref().unwrap_or(&response.languages);
+
+            txt.push_str(&format!(
+                "@lang {} {}\n",
+                languages.original, languages.translate
+            ));
+            current_languages = field.languages.as_ref();
+        }
+
+        if !tags.is_empty() {
+            txt.push_str(&format!("@tags {tags}\n"));
+        }
+
+        for comment in field.comments.iter() {
+            txt.push_str(&format!("@comment {}\n", comment.replace("//", "\\//")));
+        }
+
+        for text in field.content.iter() {
+            let mut line = match text.original.starts_with(['"', '@']) {
+                true => quote_txt(&text.original),
+                false => escape_txt(&text.original, sep, &escaped_sep),
+            };
+
+            if line.starts_with('#') {
+                line.insert(0, '\\');
+            }
+
+            if !text.translate.is_empty() {
+                let translate = match text.translate.starts_with('"') {
+                    true => quote_txt(&text.translate),
+                    false => escape_txt(&text.translate, sep, &escaped_sep),
+                };
+
+                line.push_str(&format!(" {sep} {translate}"));
+            }
+
+            txt.push_str(&wrap_line(&line, width));
+            txt.push('\n');
+        }
+
+        if !tags.is_empty() {
+            txt.push_str(&format!("@@tags {tags}\n"));
+        }
+    }
+
+    return txt;
+}
+
+/// Переносит строку длиннее `width` символов на следующие строки по границам слов.
+///
+/// Перенос ставится перед словом, после пробелов, а строка перед переносом заканчивается `\`
+/// (см. [`crate::parser_v2::ParseOptions::line_continuation`]), поэтому при чтении части
+/// склеиваются обратно в исходную строку. Слово длиннее `width` не разрывается.
+/// `width`, равный 0, отключает перенос.
+pub fn wrap_line(line: &str, width: usize) -> String {
+    let mut wrapped = String::new();
+    let mut rest = line;
+
+    while width > 0 && rest.chars().count() > width {
+        // Последнее начало слова, перед которым строка вместе с `\` помещается в `width`,
+        // а если такого нет - первое начало слова после длинного слова.
+        let mut fit = None;
+        let mut over = None;
+        let mut prev = None;
+
+        for (count, (index, ch)) in rest.char_indices().enumerate() {
+            if prev == Some(' ') && ch != ' ' && !rest[..index].trim().is_empty() {
+                if count < width {
+                    fit = Some(index);
+                } else {
+                    over = Some(index);
+                    break;
+                }
+            }
+
+            prev = Some(ch);
+        }
+
+        let Some(index) = fit.or(over) else {
+            break;
+        };
+
+        wrapped.push_str(&rest[..index]);
+        wrapped.push_str("\\\n");
+        rest = &rest[index..];
+    }
+
+    wrapped.push_str(rest);
+
+    return wrapped;
+}
+
+/// Преобразует результат парсинга в YAML.
+///
+/// Тэги записываются последовательностью в алфавитном порядке, как и в JSON.
+/// Доступно только с включенной опцией `yaml`.
+#[cfg(feature = "yaml")]
+pub fn to_yaml(response: &Response) -> Result<String, ParseError> {
+    return serde_yaml::to_string(response).map_err(|err| ParseError::Export(err.to_string()));
+}
+
+/// Схема таблицы, которую записывает [`to_parquet`].
+#[cfg(feature = "parquet")]
+const PARQUET_SCHEMA: &str = "
+message text {
+    REQUIRED BYTE_ARRAY original (UTF8);
+    REQUIRED BYTE_ARRAY translate (UTF8);
+    REQUIRED GROUP tags (LIST) {
+        REPEATED GROUP list {
+            REQUIRED BYTE_ARRAY element (UTF8);
+        }
+    }
+    REQUIRED BYTE_ARRAY original_lang (UTF8);
+    REQUIRED BYTE_ARRAY translate_lang (UTF8);
+}
+";
+
+/// Записывает результат парсинга в файл формата Parquet.
+///
+/// Каждый текст становится строкой таблицы со столбцами `original`, `translate`,
+/// `tags` (список, отсортированный по алфавиту), `original_lang` и `translate_lang`.
+/// Языки берутся из поля, если они заданы директивой `@lang` посреди файла.
+/// Доступно только с включенной опцией `parquet`.
+#[cfg(feature = "parquet")]
+pub fn to_parquet(response: &Response, path: &std::path::Path) -> Result<(), ParseError> {
+    use parquet::{
+        data_type::{ByteArray, ByteArrayType},
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    };
+    use std::{fs::File, sync::Arc};
+
+    let export_err = |err: parquet::errors::ParquetError| ParseError::Export(err.to_string());
+
+    let mut originals: Vec<ByteArray> = Default::default();
+    let mut translates: Vec<ByteArray> = Default::default();
+    let mut tags: Vec<ByteArray> = Default::default();
+    let mut tags_def: Vec<i16> = Default::default();
+    let mut tags_rep: Vec<i16> = Default::default();
+    let mut original_langs: Vec<ByteArray> = Default::default();
+    let mut translate_langs: Vec<ByteArray> = Default::default();
+
+    for field in response.fields.iter() {
+        let languages = field.languages.as_ref().unwrap_or(&response.languages);
+        let mut field_tags: Vec<&str> = field.tags.iter().map(|x| x.as_str()).collect();
+        field_tags.sort();
+
+        for text in field.content.iter() {
+            originals.push(ByteArray::from(text.original.as_str()));
+            translates.push(ByteArray::from(text.translate.as_str()));
+            original_langs.push(ByteArray::from(languages.original.as_str()));
+            translate_langs.push(ByteArray::from(languages.translate.as_str()));
+
+            // Пустой список записывается одним уровнем определения 0 без значения,
+            // каждый тэг - уровнем 1, а уровень повторения 1 продолжает список текущей строки.
+            if field_tags.is_empty() {
+                tags_def.push(0);
+                tags_rep.push(0);
+            }
+
+            for (index, tag) in field_tags.iter().enumerate() {
+                tags.push(ByteArray::from(*tag));
+                tags_def.push(1);
+                tags_rep.push(if index == 0 { 0 } else { 1 });
+            }
+        }
+    }
+
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(export_err)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+    let file = File::create(path)?;
+
+    let mut writer = SerializedFileWriter::new(file, schema, properties).map_err(export_err)?;
+    let mut row_group = writer.next_row_group().map_err(export_err)?;
+    let mut index = 0;
+
+    while let Some(mut column) = row_group.next_column().map_err(export_err)? {
+        let typed = column.typed::<ByteArrayType>();
+
+        match index {
+            0 => typed.write_batch(&originals, None, None),
+            1 => typed.write_batch(&translates, None, None),
+            2 => typed.write_batch(&tags, Some(&tags_def), Some(&tags_rep)),
+            3 => typed.write_batch(&original_langs, None, None),
+            _ => typed.write_batch(&translate_langs, None, None),
+        }
+        .map_err(export_err)?;
+
+        column.close().map_err(export_err)?;
+        index += 1;
+    }
+
+    row_group.close().map_err(export_err)?;
+    writer.close().map_err(export_err)?;
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::parser_v2::{parse_str, ParseOptions};
+
+    /// Парсит текст без проверки запрещенных символов, чтобы в текстах
+    /// могли быть символы, которые экранируются при экспорте.
+    fn parse_text(input: &str) -> Box<Response> {
+        let options = ParseOptions::builder().forbidden_chars("").build();
+
+        return parse_str(input, &options).unwrap();
+    }
+
+    #[test]
+    fn html_escapes_cells_and_marks_untranslated_rows() {
+        let response = parse_text("@lang de ru\n#a&b\n<b>Tom & Jerry</b> -- Том и Джерри\nOhne\n");
+        let html = to_html(&response);
+
+        assert!(html.contains(
+            "<tr><td>&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;</td><td>Том и Джерри</td><td>a&amp;b</td></tr>"
+        ));
+        assert!(
+            html.contains("<tr class=\"untranslated\"><td>Ohne</td><td></td><td>a&amp;b</td></tr>")
+        );
+        assert!(!html.contains("<b>Tom"));
+    }
+
+    /// Строка таблицы Parquet: оригинал, перевод, тэги и языки.
+    #[cfg(feature = "parquet")]
+    type ParquetRow = (String, String, Vec<String>, String, String);
+
+    /// Читает все строки файла Parquet, записанного [`to_parquet`].
+    #[cfg(feature = "parquet")]
+    fn read_parquet(path: &std::path::Path) -> Vec<ParquetRow> {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::{ListAccessor, RowAccessor};
+
+        let reader = SerializedFileReader::new(std::fs::File::open(path).unwrap()).unwrap();
+
+        return reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                let row = row.unwrap();
+                let list = row.get_list(2).unwrap();
+                let tags = (0..list.len())
+                    .map(|index| list.get_string(index).unwrap().to_string())
+                    .collect();
+
+                return (
+                    row.get_string(0).unwrap().to_string(),
+                    row.get_string(1).unwrap().to_string(),
+                    tags,
+                    row.get_string(3).unwrap().to_string(),
+                    row.get_string(4).unwrap().to_string(),
+                );
+            })
+            .collect();
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_has_row_per_text() {
+        let response =
+            parse_text("@lang de ru\n@tags b, a\nEins -- Один\nZwei\n@@tags b, a\nDrei -- Три\n");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("texts.parquet");
+
+        to_parquet(&response, &path).unwrap();
+
+        let rows = read_parquet(&path);
+        let tags = |names: &[&str]| names.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            (
+                "Eins".to_string(),
+                "Один".to_string(),
+                tags(&["a", "b"]),
+                "de".to_string(),
+                "ru".to_string(),
+            )
+        );
+        assert_eq!(rows[1].1, "");
+        assert_eq!(rows[2].2, tags(&[]));
+    }
+
+    #[test]
+    fn write_json_round_trips_through_bytes() {
+        let response =
+            parse_text("@lang de ru\n#a\nEins -- Один\n##a\nZwei\nBöse <tag> -- Плохо\n");
+
+        for pretty in [false, true] {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_json(&response, &mut buffer, pretty).unwrap();
+
+            let parsed: Response = serde_json::from_slice(&buffer).unwrap();
+
+            assert_eq!(
+                serde_json::to_value(&parsed).unwrap(),
+                serde_json::to_value(&*response).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn csv_quotes_commas_and_quotes() {
+        let response = parse_text("@tags b, a\nHallo -- Привет, \"друг\"\nTschüss -- Пока\n");
+
+        assert_eq!(
+            to_csv(&response),
+            "tags,original,translate\r\na;b,Hallo,\"Привет, \"\"друг\"\"\"\r\na;b,Tschüss,Пока\r\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_round_trips_with_sorted_tags() {
+        let response = parse_text("@lang de ru\n@tags zeta, alpha\nEins -- Один\nZwei\n");
+        let yaml = to_yaml(&response).unwrap();
+
+        assert!(yaml.contains("tags:\n  - alpha\n  - zeta\n"));
+
+        let parsed: Response = serde_yaml::from_str(&yaml).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&*response).unwrap()
+        );
+    }
+
+    #[test]
+    fn ios_strings_escape_quote_newline_and_backslash() {
+        let mut response =
+            parse_text("#menu\n#title\nSay */ hi -- x\n##menu\n##title\nZwei -- Два\n");
+        response.map_texts(|text| {
+            if text.original.starts_with("Say") {
+                text.translate = "Sag \"Hallo\"\nund \\ geh".to_string();
+            }
+        });
+
+        assert_eq!(
+            to_ios_strings(&response),
+            "/* Say * / hi */\n\"menu.title.0\" = \"Sag \\\"Hallo\\\"\\nund \\\\ geh\";\n\n/* Zwei */\n\"0\" = \"Два\";\n"
+        );
+    }
+
+    #[test]
+    fn ios_strings_keys_are_unique() {
+        let response = parse_text(
+            "@lang de ru\n#a\nEins -- Один\n##a\n@lang en ru\n#a\nOne -- Один\n##a\n#a\n#b\nZwei -- Два\n##a\n##b\n#a.b\nDrei -- Три\n",
+        );
+        let strings = to_ios_strings(&response);
+        let keys: Vec<&str> = strings
+            .lines()
+            .filter_map(|line| line.strip_prefix('"'))
+            .filter_map(|line| line.split('"').next())
+            .collect();
+
+        assert_eq!(keys, vec!["a.0", "a.1", "a.b.0", "a\\\\.b.0"]);
+    }
+
+    #[test]
+    fn txt_writes_lang_before_fields_with_other_languages() {
+        let response =
+            parse_text("@lang de ru\nEins -- Один\n@lang en fr\n#a\nOne -- Un\n##a\n@lang de ru\n#b\nZwei -- Два\n");
+
+        let txt = to_txt(&response);
+        let parsed = parse_text(&txt);
+
+        assert_eq!(
+            txt,
+            "@lang de ru\n\nEins -- Один\n\n@lang en fr\n@tags a\nOne -- Un\n@@tags a\n\n@lang de ru\n@tags b\nZwei -- Два\n@@tags b\n"
+        );
+        assert_eq!(parsed.fields[1].languages(), response.fields[1].languages());
+        assert_eq!(parsed.fields[2].languages(), None);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_rows_carry_field_languages() {
+        let response = parse_text("@lang de ru\nEins -- Один\n@lang en fr\nOne -- Un\n");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("texts.parquet");
+
+        to_parquet(&response, &path).unwrap();
+
+        let rows = read_parquet(&path);
+
+        assert_eq!((rows[0].3.as_str(), rows[0].4.as_str()), ("de", "ru"));
+        assert_eq!((rows[1].3.as_str(), rows[1].4.as_str()), ("en", "fr"));
+    }
+
+    #[test]
+    fn nested_json_groups_texts_by_sorted_tags() {
+        let response = parse_text(
+            "Null -- Ноль\n@tags b, a\nEins -- Один\n@@tags b, a\n#a\nZwei -- Два\n##a\n#c\nDrei -- Три\n",
+        );
+
+        assert_eq!(
+            to_nested_json(&response),
+            serde_json::json!({
+                "_root": [{ "original": "Null", "translate": "Ноль" }],
+                "a": {
+                    "b": [{ "original": "Eins", "translate": "Один" }],
+                    "_root": [{ "original": "Zwei", "translate": "Два" }],
+                },
+                "c": [{ "original": "Drei", "translate": "Три" }],
+            })
+        );
+    }
+
+    #[test]
+    fn wrapped_txt_reparses_to_same_texts() {
+        let original = "Dies ist ein ziemlich langer Satz, der auf mehrere Zeilen umbrochen wird";
+        let translate = "Это довольно длинное предложение, которое переносится на несколько строк";
+        let response = parse_text(&format!("#a\n{original} -- {translate}\nKurz -- Коротко\n"));
+
+        let txt = to_txt_wrapped(&response, 40);
+
+        assert!(txt.lines().all(|line| line.chars().count() <= 41), "{txt}");
+        assert!(txt.lines().count() > to_txt(&response).lines().count());
+
+        let options = ParseOptions::builder().line_continuation(true).build();
+        let parsed = parse_str(&txt, &options).unwrap();
+        let texts: Vec<(&str, &str)> = parsed
+            .iter_texts()
+            .map(|(_, text)| (text.original.as_str(), text.translate.as_str()))
+            .collect();
+
+        assert_eq!(texts, vec![(original, translate), ("Kurz", "Коротко")]);
+    }
+
+    #[test]
+    fn wrap_line_keeps_short_lines_and_long_words() {
+        assert_eq!(wrap_line("kurz", 40), "kurz");
+        assert_eq!(wrap_line("ein Wort", 0), "ein Wort");
+        assert_eq!(wrap_line("aaaaaaaaaa b", 4), "aaaaaaaaaa \\\nb");
+    }
+}
@@ -0,0 +1,109 @@
+use crate::parser_v2::{parse, ParseOptions};
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::Path,
+    ptr,
+};
+
+/// Парсит файл с настройками по умолчанию и возвращает результат в виде строки JSON.
+///
+/// Возвращает нулевой указатель, если `path` нулевой или не в UTF-8, если файл
+/// не удалось разобрать или если результат не удалось записать в JSON.
+/// Директивы `@include` не обрабатываются (см. [`ParseOptions::allow_include`]).
+/// Возвращенную строку нужно освободить через [`free_string`].
+/// Доступно только с включенной опцией `ffi`. Объявления для C:
+///
+/// ```c
+/// char *parse_file(const char *path);
+/// void free_string(char *string);
+/// ```
+///
+/// Динамическая библиотека собирается командой
+/// `cargo rustc --release --lib --features ffi --crate-type cdylib`.
+///
+/// # Safety
+///
+/// `path` должен быть нулевым указателем или указывать на строку, которая
+/// оканчивается нулевым байтом и не меняется до окончания вызова.
+#[no_mangle]
+pub unsafe extern "C" fn parse_file(path: *const c_char) -> *mut c_char {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+
+    // SAFETY: указатель не нулевой, остальное гарантирует вызывающая сторона.
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(x) => x,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let response = match parse(Path::new(path), &ParseOptions::default()) {
+        Ok(x) => x,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    // JSON не содержит нулевых байтов: управляющие символы в строках экранируются.
+    return match serde_json::to_string(&response).map(CString::new) {
+        Ok(Ok(json)) => json.into_raw(),
+        _ => ptr::null_mut(),
+    };
+}
+
+/// Освобождает строку, которую вернула [`parse_file`]. Нулевой указатель игнорируется.
+///
+/// # Safety
+///
+/// `string` должен быть нулевым указателем или указателем, полученным из [`parse_file`]
+/// и еще не освобожденным. После вызова указатель использовать нельзя.
+#[no_mangle]
+pub unsafe extern "C" fn free_string(string: *mut c_char) {
+    if string.is_null() {
+        return;
+    }
+
+    // SAFETY: указатель получен из `CString::into_raw` в `parse_file`
+    // и освобождается один раз, что гарантирует вызывающая сторона.
+    drop(unsafe { CString::from_raw(string) });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn parse_file_returns_json_through_raw_pointers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.txt");
+        fs::write(&path, "#a\nEins -- Один\n").unwrap();
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let json = unsafe { parse_file(path.as_ptr()) };
+        assert!(!json.is_null());
+
+        let text = unsafe { CStr::from_ptr(json) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { free_string(json) };
+
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["fields"][0]["tags"][0], "a");
+        assert_eq!(value["fields"][0]["content"][0]["translate"], "Один");
+    }
+
+    #[test]
+    fn parse_file_returns_null_on_failure() {
+        let missing = CString::new("/nonexistent/file-parser/input.txt").unwrap();
+
+        assert!(unsafe { parse_file(missing.as_ptr()) }.is_null());
+        assert!(unsafe { parse_file(ptr::null()) }.is_null());
+
+        let not_utf8 = CString::new(vec![0xff, 0xfe]).unwrap();
+        assert!(unsafe { parse_file(not_utf8.as_ptr()) }.is_null());
+
+        unsafe { free_string(ptr::null_mut()) };
+    }
+}
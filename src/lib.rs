@@ -0,0 +1,11 @@
+#![allow(clippy::needless_return)]
+
+pub mod config;
+pub mod export;
+pub mod parser_v2;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,29 +1,365 @@
-#[macro_use]
-extern crate dotenv_codegen;
+#![allow(clippy::needless_return)]
 
-mod parser_v2;
-use parser_v2::parse;
+use file_parser::config::{Config, CONFIG_FILE};
+use file_parser::export::{to_txt, write_json};
+use file_parser::parser_v2::{
+    parse, parse_reader, ParseOptions, Response, SeparatorMode, Stats, ValidationReport,
+};
 
-use std::{fs::OpenOptions, io::Write, path::Path};
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+    process,
+};
 
-fn main() {
-    let path = Path::new("B1-K1.txt");
-    let result_path = Path::new("result.json");
+/// Язык оригинала, если он не задан ни флагом, ни в файле настроек.
+const DEFAULT_ORIGINAL: &str = "DE";
 
-    let fields = match parse(path, "DE", "RU") {
-        Ok(x) => x,
-        Err(_) => {
-            println!("ошибка открытия файла");
-            return;
+/// Язык перевода, если он не задан ни флагом, ни в файле настроек.
+const DEFAULT_TRANSLATE: &str = "RU";
+
+const USAGE: &str = "Использование: file-parser [--input <файл>] [--output <файл>] [--original <язык>] [--translate <язык>] [--split-from first|last] [--check] [--stats] [--split-by-tag <каталог>] [--reverse]
+
+  --input      путь до файла для парсинга, `-` - стандартный ввод (по умолчанию `-`)
+  --output     путь до файла с результатом, `-` - стандартный вывод (по умолчанию `-`)
+  --original   идентификатор языка оригинала (по умолчанию из `fileparser.json` или `DE`)
+  --translate  идентификатор языка перевода (по умолчанию из `fileparser.json` или `RU`)
+  --split-from по какому из нескольких разделителей в строке отделять перевод:
+               `first` - по первому (по умолчанию), `last` - по последнему
+  --check      только проверить файл: вывести ошибки и предупреждения
+               и завершиться с кодом 1, если есть ошибки
+  --stats      вывести вместо JSON сводку: количество полей, текстов, текстов
+               без перевода, строк с ошибками и самые частые тэги
+  --split-by-tag
+               записать вместо `--output` по файлу `<тэг>.json` в указанный
+               каталог для каждого тэга; поле с несколькими тэгами попадает
+               в каждый из файлов, поля без тэгов не записываются;
+               если имена файлов двух тэгов совпадают, ничего не записывается
+  --reverse    прочитать результат парсинга в формате JSON и записать его
+               обратно в текстовом формате
+
+Файл `fileparser.json` в рабочем каталоге может задать `default_separator`,
+`original_language`, `translate_language` и `forbidden_chars`. Флаги имеют
+приоритет над ним, а директивы `@sep` и `@lang` в файле - над флагами.
+Директивы `@include` обрабатываются только для файла из `--input` (не для
+стандартного ввода) и только с путями внутри его каталога.";
+
+/// Аргументы командной строки.
+///
+/// Языки, не указанные флагами, берутся из файла настроек, а если их нет и там,
+/// используются [`DEFAULT_ORIGINAL`] и [`DEFAULT_TRANSLATE`].
+struct Args {
+    input: String,
+    output: String,
+    original: Option<String>,
+    translate: Option<String>,
+    split_from: String,
+    check: bool,
+    stats: bool,
+    split_by_tag: Option<String>,
+    reverse: bool,
+}
+
+/// Разбирает аргументы командной строки.
+///
+/// Возвращает [`Err`] с текстом ошибки, если встретился неизвестный флаг
+/// или у флага нет значения.
+fn parse_args(mut raw: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args {
+        input: "-".to_string(),
+        output: "-".to_string(),
+        original: None,
+        translate: None,
+        split_from: "first".to_string(),
+        check: false,
+        stats: false,
+        split_by_tag: None,
+        reverse: false,
+    };
+
+    while let Some(flag) = raw.next() {
+        let target = match flag.as_str() {
+            "--input" | "-i" => &mut args.input,
+            "--output" | "-o" => &mut args.output,
+            "--original" => args.original.insert(String::new()),
+            "--translate" => args.translate.insert(String::new()),
+            "--split-from" => &mut args.split_from,
+            "--split-by-tag" => args.split_by_tag.insert(String::new()),
+            "--check" => {
+                args.check = true;
+                continue;
+            }
+            "--stats" => {
+                args.stats = true;
+                continue;
+            }
+            "--reverse" => {
+                args.reverse = true;
+                continue;
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("неизвестный аргумент `{flag}`\n\n{USAGE}")),
+        };
+
+        *target = match raw.next() {
+            Some(value) => value,
+            None => return Err(format!("не указано значение для `{flag}`")),
+        };
+    }
+
+    if args.split_from != "first" && args.split_from != "last" {
+        return Err(format!(
+            "неизвестное значение `{}` для `--split-from`, ожидается `first` или `last`",
+            args.split_from
+        ));
+    }
+
+    return Ok(args);
+}
+
+/// Парсит файл или стандартный ввод, если вместо пути передан `-`.
+fn read_input(input: &str, options: &ParseOptions) -> Result<Box<Response>, String> {
+    if input == "-" {
+        return parse_reader(io::stdin().lock(), options).map_err(|err| err.to_string());
+    }
+
+    return parse(Path::new(input), options).map_err(|err| err.to_string());
+}
+
+/// Читает результат парсинга в формате JSON из файла или стандартного ввода,
+/// если вместо пути передан `-`.
+fn read_json(input: &str) -> Result<Response, String> {
+    let parsed = match input {
+        "-" => serde_json::from_reader(io::stdin().lock()),
+        _ => {
+            let file = File::open(input).map_err(|err| err.to_string())?;
+            serde_json::from_reader(io::BufReader::new(file))
         }
     };
 
-    OpenOptions::new()
+    return parsed.map_err(|err| format!("ошибка чтения JSON: {err}"));
+}
+
+/// Записывает результат в файл или в стандартный вывод, если вместо пути передан `-`.
+fn write_output(output: &str, response: &Response) -> io::Result<()> {
+    return write_to(output, |writer| {
+        write_json(response, &mut *writer, true)?;
+        return writer.write_all(b"\n");
+    });
+}
+
+/// Записывает отчет о проверке в файл или в стандартный вывод, если вместо пути передан `-`.
+fn write_report(output: &str, report: &ValidationReport) -> io::Result<()> {
+    return write_to(output, |writer| {
+        serde_json::to_writer_pretty(&mut *writer, report)?;
+        return writer.write_all(b"\n");
+    });
+}
+
+/// Записывает каждую часть результата, разбитого по тэгам, в файл `<тэг>.json`
+/// в каталоге `dir`, создавая каталог, если его нет.
+///
+/// Если имена файлов двух тэгов совпадают (например, `a b` и `a_b`, или `A` и `a`
+/// в файловой системе без учета регистра), ничего не записывается и возвращается ошибка.
+fn write_split(dir: &str, response: &Response) -> io::Result<()> {
+    let parts = response.split_by_tag();
+    let mut stems: HashMap<String, &str> = HashMap::new();
+
+    for tag in parts.keys() {
+        let stem = file_stem(tag);
+
+        if let Some(other) = stems.insert(stem.to_lowercase(), tag) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("тэги `{other}` и `{tag}` записываются в один файл `{stem}.json`"),
+            ));
+        }
+    }
+
+    let dir = Path::new(dir);
+    fs::create_dir_all(dir)?;
+
+    for (tag, part) in parts.iter() {
+        let path = dir.join(format!("{}.json", file_stem(tag)));
+        write_output(&path.to_string_lossy(), part)?;
+    }
+
+    return Ok(());
+}
+
+/// Заменяет в имени тэга символы, недопустимые в имени файла, на `_`.
+fn file_stem(tag: &str) -> String {
+    return tag
+        .chars()
+        .map(|x| match x.is_alphanumeric() || "-_.".contains(x) {
+            true => x,
+            false => '_',
+        })
+        .collect();
+}
+
+/// Количество самых частых тэгов в сводке `--stats`.
+const TOP_TAGS: usize = 10;
+
+/// Записывает сводку по результату парсинга в файл или в стандартный вывод,
+/// если вместо пути передан `-`.
+fn write_stats(output: &str, stats: &Stats) -> io::Result<()> {
+    return write_to(output, |writer| {
+        writeln!(writer, "Полей:             {}", stats.fields)?;
+        writeln!(writer, "Текстов:           {}", stats.texts)?;
+        writeln!(writer, "Без перевода:      {}", stats.empty_translations)?;
+        writeln!(writer, "Строк с ошибками:  {}", stats.errors)?;
+
+        let top = stats.top_tags(TOP_TAGS);
+
+        if top.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(writer, "Частые тэги:")?;
+
+        let width = top.iter().map(|(tag, _)| tag.chars().count()).max();
+
+        for (tag, texts) in top {
+            writeln!(
+                writer,
+                "  {tag:<width$}  {texts}",
+                width = width.unwrap_or(0)
+            )?;
+        }
+
+        return Ok(());
+    });
+}
+
+/// Открывает файл или стандартный вывод и передает его в `write`.
+fn write_to<F>(output: &str, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    if output == "-" {
+        let mut stdout = io::stdout().lock();
+        write(&mut stdout)?;
+        return stdout.flush();
+    }
+
+    let file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
-        .open(result_path)
-        .expect("Error opening")
-        .write(serde_json::to_string_pretty(&fields).unwrap().as_bytes())
-        .unwrap();
+        .open(output)?;
+
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)?;
+
+    return writer.flush();
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(x) => x,
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(2);
+        }
+    };
+
+    if args.reverse {
+        let response = match read_json(&args.input) {
+            Ok(x) => x,
+            Err(message) => {
+                eprintln!("{message}");
+                process::exit(1);
+            }
+        };
+
+        let txt = to_txt(&response);
+
+        if write_to(&args.output, |writer| writer.write_all(txt.as_bytes())).is_err() {
+            eprintln!("ошибка записи результата");
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    let config = match Config::discover(Path::new(".")) {
+        Ok(x) => x.unwrap_or_default(),
+        Err(message) => {
+            eprintln!("{CONFIG_FILE}: {message}");
+            process::exit(1);
+        }
+    };
+
+    let mut builder = config.apply(
+        ParseOptions::builder()
+            .original_language(DEFAULT_ORIGINAL)
+            .translate_language(DEFAULT_TRANSLATE),
+    );
+
+    if let Some(original) = args.original {
+        builder = builder.original_language(original);
+    }
+
+    if let Some(translate) = args.translate {
+        builder = builder.translate_language(translate);
+    }
+
+    let options = builder
+        .split_from(match args.split_from.as_str() {
+            "last" => SeparatorMode::Last,
+            _ => SeparatorMode::First,
+        })
+        // Для стандартного ввода пути включений отсчитывались бы от рабочего каталога.
+        .allow_include(args.input != "-")
+        .build();
+
+    let fields = match read_input(&args.input, &options) {
+        Ok(x) => x,
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(1);
+        }
+    };
+
+    if args.check {
+        let report = ValidationReport::from(*fields);
+
+        if write_report(&args.output, &report).is_err() {
+            eprintln!("ошибка записи результата");
+            process::exit(1);
+        }
+
+        if !report.is_ok {
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    if args.stats {
+        if write_stats(&args.output, &fields.stats()).is_err() {
+            eprintln!("ошибка записи результата");
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(dir) = &args.split_by_tag {
+        if let Err(err) = write_split(dir, &fields) {
+            eprintln!("ошибка записи результата в `{dir}`: {err}");
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    if write_output(&args.output, &fields).is_err() {
+        eprintln!("ошибка записи результата");
+        process::exit(1);
+    }
 }
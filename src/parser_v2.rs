@@ -1,244 +1,6276 @@
 use regex::Regex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize, Serializer};
 
 use std::{
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader, Seek, SeekFrom},
-    path::Path,
+    borrow::{Borrow, Cow},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    mem,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    slice,
+    str::FromStr,
 };
 
-/// Структура, описывающая результат парсинга файла с помощью парсера `v2`.
+/// Перечисление, описывающее ошибки, из-за которых парсинг не может быть завершен.
 ///
-/// Структура содержит информацию о языках (`languages`), полях (`fields`),
-/// и ошибках (`errors`), которые были найдены во время парсинга.
-#[derive(Serialize)]
-pub struct Response {
-    languages: Languages,
-    fields: Vec<Field>,
-    errors: Vec<ErrorLine>,
+/// Ошибки в содержимом файла (запрещенные символы и т.п.) сюда не относятся:
+/// они собираются в [`Response`] и не прерывают парсинг (кроме режима
+/// [`ParseOptions::fail_fast`]).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// Ошибка ввода-вывода при открытии или чтении файла.
+    Io(io::Error),
+    /// Ошибка при преобразовании результата в другой формат.
+    Export(String),
+    /// Недопустимый разделитель (например, пустой в директиве `@sep`
+    /// или совпадающий с префиксом тэга или маркером комментария).
+    InvalidSeparator(String),
+    /// Строка с тэгами одновременно похожа на открывающую и закрывающую директиву.
+    AmbiguousDirective { line: u32 },
+    /// Превышено ограничение [`ParseOptions::max_lines`] или [`ParseOptions::max_bytes`].
+    LimitExceeded { line: u32 },
+    /// Превышено ограничение [`ParseOptions::max_active_tags`]
+    /// (при [`ParseOptions::fail_on_too_many_tags`]).
+    TooManyTags { line: u32 },
+    /// Директива `@include` образует цикл: файл прямо или косвенно включает сам себя.
+    IncludeCycle(String),
+    /// Путь в директиве `@include` абсолютный или содержит `..`
+    /// (без [`ParseOptions::allow_include_outside`]).
+    ForbiddenInclude(String),
+    /// Неизвестная или неподдерживаемая кодировка в [`ParseOptions::encoding`]
+    /// или в директиве `@charset`.
+    UnknownEncoding(String),
+    /// Язык не входит в список ISO 639-1 или код языка не похож на тэг BCP 47
+    /// (при [`ParseOptions::validate_languages`]).
+    UnknownLanguage(String),
+    /// Файл настроек (см. [`crate::config`]) не удалось разобрать.
+    InvalidConfig(String),
+    /// Строка начинается с `@`, но не является известной директивой
+    /// (при [`ParseOptions::strict_directives`]).
+    UnknownDirective { line: u32, directive: String },
+    /// Известная директива записана с ошибкой, например `@comment` или `@include`
+    /// без значения (при [`ParseOptions::strict_directives`]).
+    InvalidDirective { line: u32, directive: String },
+    /// Строка с запрещенными символами (при [`ParseOptions::fail_fast`]).
+    InvalidContent { line: u32, string: String },
 }
 
-/// Структура, описывающая отдельный текст для перевода.
-///
-/// Структура содержит оригинальный текст (`original`) и его перевод (`translate`).
-#[derive(Serialize, Clone)]
-struct Text {
-    original: String,
-    translate: String,
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "ошибка чтения файла: {err}"),
+            ParseError::Export(err) => write!(f, "ошибка экспорта: {err}"),
+            ParseError::InvalidSeparator(sep) => write!(f, "недопустимый разделитель `{sep}`"),
+            ParseError::AmbiguousDirective { line } => {
+                write!(f, "неоднозначная директива тэгов в строке {line}")
+            }
+            ParseError::LimitExceeded { line } => {
+                write!(
+                    f,
+                    "превышено ограничение размера входных данных в строке {line}"
+                )
+            }
+            ParseError::TooManyTags { line } => {
+                write!(f, "слишком много активных тэгов в строке {line}")
+            }
+            ParseError::IncludeCycle(path) => {
+                write!(f, "циклическое включение файла `{path}`")
+            }
+            ParseError::ForbiddenInclude(path) => {
+                write!(f, "недопустимый путь во включении файла `{path}`")
+            }
+            ParseError::UnknownEncoding(label) => write!(f, "неизвестная кодировка `{label}`"),
+            ParseError::UnknownLanguage(code) => write!(f, "неизвестный код языка `{code}`"),
+            ParseError::InvalidConfig(err) => write!(f, "ошибка в файле настроек: {err}"),
+            ParseError::UnknownDirective { line, directive } => {
+                write!(f, "неизвестная директива `{directive}` в строке {line}")
+            }
+            ParseError::InvalidDirective { line, directive } => {
+                write!(
+                    f,
+                    "директива `{directive}` в строке {line} записана с ошибкой"
+                )
+            }
+            ParseError::InvalidContent { line, string } => {
+                write!(f, "запрещенные символы в строке {line}: `{string}`")
+            }
+        }
+    }
 }
 
-/// Структура, описывающая поле в файле.
-///
-/// Структура содержит набор тегов (`tags`), с помощью которых
-/// поле можно идентифицировать, и вектор текстов для перевода (`content`).
-#[derive(Serialize)]
-struct Field {
-    tags: HashSet<String>,
-    content: Vec<Text>,
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
-/// Структура, описывающая языки, используемые в файле для перевода.
-///
-/// Структура содержит идентификатор языка оригинала (`original`) и идентификатор языка перевода (`translate`).
-#[derive(Serialize)]
-struct Languages {
-    original: String,
-    translate: String,
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
 }
 
-/// Структура, описывающая строку с ошибкой при парсинге файла.
+/// Структура, описывающая результат парсинга файла с помощью парсера `v2`.
 ///
-/// Структура содержит номер строки (`line`), в которой была найдена ошибка,
-/// и вектор индексов столбцов (`columns`), в которых были найдены ошибки,
-/// а также саму строку с ошибкой (`string`).
-#[derive(Serialize)]
-struct ErrorLine {
-    line: i32,
-    columns: Vec<usize>,
-    string: String,
-}
-
-/// Описывает функцию, которая парсит файл и создает объект-ответ.
+/// Структура содержит информацию о языках (`languages`), фактически использованном
+/// разделителе (`separator`: из директивы `@sep`, определенный автоматически или
+/// по умолчанию), полях (`fields`), ошибках (`errors`) и предупреждениях (`warnings`),
+/// которые были найдены во время парсинга. В `all_tags` хранятся в алфавитном порядке
+/// все тэги, которые открывались в файле, в том числе потом закрытые и не попавшие ни в одно поле.
+/// При [`ParseOptions::collect_directives`] в `directives` записываются все распознанные
+/// директивы файла (без директив из файлов, включенных через `@include`).
+/// Если часть ошибок отброшена из-за [`ParseOptions::max_errors`], `errors_truncated` равен `true`.
+/// При [`ParseOptions::keep_header_comments`] в `header_comments` хранятся комментарии
+/// из начала файла (до первой строки с текстом или тэгом) без маркера `//`.
 ///
-/// * `path_to_file: &`[`Path`] - путь до файла, который нужно парсить.
-/// * `original_lang: &`[`str`] - идентификатор языка оригинала.
-/// * `translate_lang: &`[`str`] - идентификатор языка перевода.
+/// Имена ключей в JSON совпадают с именами полей и являются частью внешнего формата,
+/// поэтому не меняются без необходимости. Ключи, помеченные `?`, пропускаются,
+/// если значение пустое:
 ///
-/// Функция возвращает `Result<Box<Response>, ()>`, где [`Ok`] - успешно
-/// пропарсенный объект-ответ, а [`Err`] - ошибка при чтении или парсинге файла.
-pub fn parse(
-    path_to_file: &Path,
-    original_lang: &str,
-    translate_lang: &str,
-) -> Result<Box<Response>, ()> {
-    let file = match File::open(path_to_file) {
-        Ok(file) => file,
-        Err(_) => return Err(()),
-    };
+/// ```text
+/// {
+///   "languages": { "original", "translate" },
+///   "separator",
+///   "header_comments"?,
+///   "fields": [{
+///     "tags",
+///     "content": [{ "original", "translate", "translates"?, "sep_offset"?, "line", "raw"? }],
+///     "comments"?,
+///     "languages"?: { "original", "translate" }
+///   }],
+///   "all_tags",
+///   "errors": [{ "line", "column"?, "columns", "spans": [{ "start_char", "end_char" }], "string", "file"? }],
+///   "errors_truncated"?,
+///   "warnings"?: [{ "line", "column"?, "kind", "message", "file"? }],
+///   "directives"?: [{ "kind", "line", "column"?, "raw" }]
+/// }
+/// ```
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub(crate) languages: Languages,
+    #[serde(default)]
+    pub(crate) separator: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) header_comments: Vec<String>,
+    pub(crate) fields: Vec<Field>,
+    #[serde(default)]
+    pub(crate) all_tags: BTreeSet<Tag>,
+    pub(crate) errors: Vec<ErrorLine>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) errors_truncated: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) warnings: Vec<Warning>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) directives: Vec<DirectiveInfo>,
+}
 
-    let mut reader = BufReader::new(&file);
+impl Response {
+    /// Создает пустой объект-ответ с языками из настроек.
+    fn new(options: &ParseOptions) -> Response {
+        return Response {
+            fields: Default::default(),
+            all_tags: Default::default(),
+            errors: Default::default(),
+            errors_truncated: false,
+            warnings: Default::default(),
+            directives: Default::default(),
+            separator: Default::default(),
+            header_comments: Default::default(),
+            languages: match &options.force_languages {
+                Some(languages) => languages.clone(),
+                None => Languages::new(
+                    options.original_language.as_str(),
+                    options.translate_language.as_str(),
+                ),
+            },
+        };
+    }
 
-    let mut response = Response {
-        fields: Default::default(),
-        errors: Default::default(),
-        languages: Languages {
-            original: original_lang.to_string(),
-            translate: translate_lang.to_string(),
-        },
-    };
+    /// Строит обратный индекс: каждому непустому переводу (`translate`)
+    /// сопоставляет список оригиналов, которые на него переводятся.
+    ///
+    /// Позволяет найти случаи, когда один и тот же перевод используется
+    /// для разных исходных текстов.
+    pub fn translate_index(&self) -> HashMap<String, Vec<String>> {
+        let mut index: HashMap<String, Vec<String>> = Default::default();
 
-    let mut content: Vec<Text> = Default::default();
-    let mut tags: HashSet<String> = Default::default();
+        for field in self.fields.iter() {
+            for text in field.content.iter() {
+                if text.translate.is_empty() {
+                    continue;
+                }
 
-    let mut string: String;
-    let mut num_line: i32 = 0;
+                index
+                    .entry(text.translate.clone())
+                    .or_default()
+                    .push(text.original.clone());
+            }
+        }
 
-    let sep = get_separator(&mut reader);
+        return index;
+    }
 
-    let tags_reg = Regex::new(r"(^#{1,2}\w+)|(^@{1,2}tags)").unwrap();
-    let error_reg = Regex::new("[<>:\"/\\|*]+").unwrap();
-    let remove_tags_reg = Regex::new(r"^(#{2})|(@{2}tags\s)").unwrap();
+    /// Разбивает объект-ответ на несколько частей, в каждой из которых
+    /// не больше `max_entries` текстов (минимум один).
+    ///
+    /// Языки и тэги сохраняются в каждой части. Поле целиком переносится в следующую часть,
+    /// если не помещается в текущую, и делится только тогда, когда само превышает лимит.
+    /// Ошибки и предупреждения парсинга в части не копируются.
+    pub fn chunk(&self, max_entries: usize) -> Vec<Response> {
+        let max_entries = max_entries.max(1);
 
-    for line in reader.lines() {
-        num_line += 1;
+        let mut chunks: Vec<Response> = Default::default();
+        let mut current: Vec<Field> = Default::default();
+        let mut count = 0;
 
-        string = match line {
-            Ok(x) => x.trim().to_string(),
-            Err(_) => "".to_string(),
-        };
+        for field in self.fields.iter() {
+            for piece in field.content.chunks(max_entries) {
+                if count + piece.len() > max_entries {
+                    chunks.push(self.with_fields(mem::take(&mut current)));
+                    count = 0;
+                }
 
-        if skip_line_else(&string) {
-            continue;
+                count += piece.len();
+                current.push(Field {
+                    tags: field.tags.clone(),
+                    content: piece.to_vec(),
+                    comments: field.comments.clone(),
+                    languages: field.languages.clone(),
+                });
+            }
         }
 
-        if error_reg.is_match(&string) {
-            let mut error = ErrorLine {
-                line: num_line,
-                columns: Default::default(),
-                string: string.to_string(),
-            };
+        if !current.is_empty() {
+            chunks.push(self.with_fields(current));
+        }
 
-            for column in error_reg.find_iter(&string) {
-                error.columns.push(column.start());
-            }
+        return chunks;
+    }
 
-            response.errors.push(error);
+    /// Добавляет к объекту-ответу поля, ошибки и предупреждения из `other`.
+    ///
+    /// Поля с одинаковым набором тэгов объединяются так же, как при парсинге
+    /// (см. [`update_response`]). Если языки не совпадают, языки `self` сохраняются,
+    /// а в предупреждения добавляется [`WarningKind::LanguageMismatch`].
+    pub fn merge(&mut self, mut other: Response) {
+        if self.separator.is_empty() {
+            self.separator = mem::take(&mut other.separator);
+        }
 
-            continue;
+        if self.header_comments.is_empty() {
+            self.header_comments = mem::take(&mut other.header_comments);
         }
 
-        if tags_reg.is_match(string.as_str()) {
-            let parsed_tags = parse_tags(&string);
+        let same_languages = self.languages.original == other.languages.original
+            && self.languages.translate == other.languages.translate;
+
+        if !same_languages {
+            self.warnings.push(Warning {
+                location: Location::new(0),
+                kind: WarningKind::LanguageMismatch,
+                message: format!(
+                    "языки `{} -> {}` не совпадают с `{} -> {}`, оставлены последние",
+                    other.languages.original,
+                    other.languages.translate,
+                    self.languages.original,
+                    self.languages.translate,
+                ),
+                file: None,
+            });
+        }
 
-            update_response(&mut response, &mut content, &mut tags);
+        for field in other.fields.drain(..) {
+            merge_field(self, field, true);
+        }
 
-            if remove_tags_reg.is_match(&string) {
-                substract_tags(&mut tags, &parsed_tags);
-            } else {
-                extend_tags(&mut tags, &parsed_tags);
+        self.all_tags.append(&mut other.all_tags);
+        self.errors.append(&mut other.errors);
+        self.errors_truncated |= other.errors_truncated;
+        self.warnings.append(&mut other.warnings);
+    }
+
+    /// Применяет `f` к каждому тексту всех полей, изменяя тексты на месте
+    /// (например, чтобы убрать лишнюю пунктуацию или перевести перевод в верхний регистр).
+    pub fn map_texts<F: FnMut(&mut Text)>(&mut self, mut f: F) {
+        for field in self.fields.iter_mut() {
+            field.content.iter_mut().for_each(&mut f);
+        }
+    }
+
+    /// Возвращает поля в порядке их появления в файле.
+    pub fn fields(&self) -> &[Field] {
+        return &self.fields;
+    }
+
+    /// Возвращает поля, у которых среди тэгов есть `tag`, в порядке их появления в файле.
+    pub fn fields_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Field> {
+        return self
+            .fields
+            .iter()
+            .filter(move |field| field.tags.contains(tag));
+    }
+
+    /// Разбивает объект-ответ по тэгам: для каждого тэга из `all_tags` возвращает
+    /// объект-ответ только с полями, у которых есть этот тэг (см. [`Response::fields_with_tag`]).
+    ///
+    /// Поле с несколькими тэгами попадает в каждую из частей, поля без тэгов - ни в одну.
+    /// В `all_tags` части остаются только тэги ее полей. Ошибки и предупреждения
+    /// парсинга в части не копируются.
+    pub fn split_by_tag(&self) -> BTreeMap<String, Response> {
+        let mut parts: BTreeMap<String, Response> = Default::default();
+
+        for tag in self.all_tags.iter() {
+            let fields: Vec<Field> = self.fields_with_tag(tag.as_str()).cloned().collect();
+
+            if fields.is_empty() {
+                continue;
             }
-        } else {
-            let (original, translate) = match string.split_once(sep.as_str()) {
-                Some(x) => x,
-                None => (string.as_str(), ""),
-            };
 
-            content.push(Text {
-                original: String::from(original.trim()),
-                translate: String::from(translate.trim()),
-            });
+            let mut part = self.with_fields(fields);
+            part.all_tags = part
+                .fields
+                .iter()
+                .flat_map(|field| field.tags.iter().cloned())
+                .collect();
+
+            parts.insert(tag.to_string(), part);
         }
+
+        return parts;
     }
 
-    update_response(&mut response, &mut content, &mut tags);
+    /// Возвращает итератор по всем текстам всех полей вместе с тэгами поля
+    /// в порядке их появления в файле. То же самое дает `for (tags, text) in &response`.
+    pub fn iter_texts(&self) -> Texts<'_> {
+        return Texts {
+            fields: self.fields.iter(),
+            current: None,
+        };
+    }
 
-    return Ok(Box::new(response));
-}
+    /// Возвращает строки с ошибками в порядке их появления в файле.
+    pub fn errors(&self) -> &[ErrorLine] {
+        return &self.errors;
+    }
 
-/// Определяет, пустая ли строка или начинается ли она с комментария
-/// (строка начинается с "//").
-fn skip_line_else(string: &String) -> bool {
-    let reg = Regex::new(r"^//|@sep").unwrap();
-    return reg.is_match(string) || string.is_empty();
-}
+    /// Возвращает предупреждения в порядке их появления в файле.
+    pub fn warnings(&self) -> &[Warning] {
+        return &self.warnings;
+    }
 
-/// Описывает функцию, которая добавляет в объект-ответ новый элемент [`Field`], если в нём нет такого же набора тэгов.
-/// Если же есть, то добавляет к нему содержимое из переданного вектора [`Field::content`].
-/// Если вектор не пуст, то очищает его после добавления.
-fn update_response(response: &mut Response, content: &mut Vec<Text>, tags: &mut HashSet<String>) {
-    if !content.is_empty() {
-        for field in response.fields.iter_mut() {
-            if *tags == field.tags {
-                field.content.append(content);
-                return;
+    /// Возвращает `true`, если часть ошибок отброшена из-за [`ParseOptions::max_errors`].
+    pub fn errors_truncated(&self) -> bool {
+        return self.errors_truncated;
+    }
+
+    /// Возвращает комментарии из начала файла (см. [`ParseOptions::keep_header_comments`]).
+    pub fn header_comments(&self) -> &[String] {
+        return &self.header_comments;
+    }
+
+    /// Возвращает директивы файла (см. [`ParseOptions::collect_directives`]).
+    pub fn directives(&self) -> &[DirectiveInfo] {
+        return &self.directives;
+    }
+
+    /// Возвращает количество полей.
+    pub fn field_count(&self) -> usize {
+        return self.fields.len();
+    }
+
+    /// Возвращает `true`, если в результате нет ни одного поля.
+    ///
+    /// Пустой результат не означает, что парсинг прошел без ошибок: в файле из одних
+    /// строк с запрещенными символами полей нет, но `errors` не пуст. Пустой результат
+    /// без ошибок получается, например, из файла только с комментариями и директивами.
+    pub fn is_empty(&self) -> bool {
+        return self.fields.is_empty();
+    }
+
+    /// Возвращает количество текстов во всех полях.
+    pub fn total_texts(&self) -> usize {
+        return self.fields.iter().map(Field::content_len).sum();
+    }
+
+    /// Подсчитывает статистику по результату парсинга (см. [`Stats`]).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            fields: self.fields.len(),
+            errors: self.errors.len(),
+            ..Default::default()
+        };
+
+        for field in self.fields.iter() {
+            stats.texts += field.content.len();
+            stats.empty_translations += field
+                .content
+                .iter()
+                .filter(|text| text.translate.is_empty())
+                .count();
+            stats.tags.extend(field.tags.iter().map(Tag::to_string));
+
+            for tag in field.tags.iter() {
+                *stats.tag_texts.entry(tag.to_string()).or_default() += field.content.len();
             }
         }
 
-        response.fields.push(Field {
-            tags: tags.clone(),
-            content: content.clone(),
-        });
+        return stats;
+    }
 
-        content.clear();
+    /// Создает объект-ответ с теми же языками и переданными полями, но без ошибок и предупреждений.
+    fn with_fields(&self, fields: Vec<Field>) -> Response {
+        return Response {
+            languages: self.languages.clone(),
+            separator: self.separator.clone(),
+            header_comments: self.header_comments.clone(),
+            fields,
+            all_tags: self.all_tags.clone(),
+            errors: Default::default(),
+            errors_truncated: false,
+            warnings: Default::default(),
+            directives: Default::default(),
+        };
     }
 }
 
-/// Вычитает из набора тэгов набор тэгов, которые должны быть вычеркнуты
-fn substract_tags(target_tags: &mut HashSet<String>, tags_to_substract: &Box<HashSet<String>>) {
-    for tag in tags_to_substract.iter() {
-        target_tags.remove(tag);
+/// Парсит текст с настройками по умолчанию (см. [`parse_str`]).
+///
+/// ```
+/// use file_parser::parser_v2::Response;
+///
+/// // Разделитель по умолчанию - `--`, поэтому без директивы `@sep` строка целиком - оригинал.
+/// let response = "a = b".parse::<Response>().unwrap();
+/// assert_eq!(response.fields()[0].content()[0].original, "a = b");
+///
+/// let response: Response = "@sep =\na = b".parse().unwrap();
+/// let text = &response.fields()[0].content()[0];
+/// assert_eq!((text.original.as_str(), text.translate.as_str()), ("a", "b"));
+/// ```
+impl FromStr for Response {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Response, ParseError> {
+        return parse_str(input, &ParseOptions::default()).map(|response| *response);
     }
 }
 
-/// Добавляет в набор тэгов набор тэгов, которые должны быть добавлены
-fn extend_tags(target_tags: &mut HashSet<String>, additional_tags: &Box<HashSet<String>>) {
-    for tag in additional_tags.iter() {
-        target_tags.insert(tag.clone());
+impl<'a> IntoIterator for &'a Response {
+    type Item = (&'a HashSet<Tag>, &'a Text);
+    type IntoIter = Texts<'a>;
+
+    fn into_iter(self) -> Texts<'a> {
+        return self.iter_texts();
     }
 }
 
-/// Определяет набор тэгов из строки. Если строка начинается с символа @, то разбивает
-/// остаток строки на набор тэгов, разделенных запятыми, и возвращает их в виде [`HashSet`].
-/// Если строка начинается с символа #, то возвращает [`HashSet`], содержащий одну строку, без символа # в начале.
-///
-fn parse_tags(string: &String) -> Box<HashSet<String>> {
-    let mut tags: HashSet<String> = Default::default();
-    if string.starts_with("@") {
-        let raw = string.replace("@", "")[4..].to_string();
-        let collect: HashSet<&str> = raw.split(",").map(|x| x.trim()).collect();
+/// Итератор по парам из тэгов поля и текста для всех полей [`Response`]
+/// (см. [`Response::iter_texts`]).
+pub struct Texts<'a> {
+    fields: slice::Iter<'a, Field>,
+    current: Option<(&'a HashSet<Tag>, slice::Iter<'a, Text>)>,
+}
+
+impl<'a> Iterator for Texts<'a> {
+    type Item = (&'a HashSet<Tag>, &'a Text);
 
-        for tag in collect {
-            if !tag.is_empty() {
-                tags.insert(tag.to_string());
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((tags, texts)) = self.current.as_mut() {
+                if let Some(text) = texts.next() {
+                    return Some((*tags, text));
+                }
             }
+
+            let field = self.fields.next()?;
+            self.current = Some((&field.tags, field.content.iter()));
         }
-    } else if string.starts_with("#") {
-        let tag = string.replace("#", "").trim().to_string();
-        tags.insert(tag);
+    }
+}
+
+/// Структура, описывающая результат проверки файла (см. [`validate`]).
+///
+/// Структура содержит ошибки (`errors`) и предупреждения (`warnings`) парсинга без самих полей,
+/// а также признак `is_ok`, который равен `true`, если ошибок нет (предупреждения не учитываются).
+#[derive(Serialize)]
+pub struct ValidationReport {
+    pub is_ok: bool,
+    pub(crate) errors: Vec<ErrorLine>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) warnings: Vec<Warning>,
+}
+
+impl ValidationReport {
+    /// Возвращает строки с ошибками.
+    pub fn errors(&self) -> &[ErrorLine] {
+        return &self.errors;
     }
 
-    return Box::new(tags);
+    /// Возвращает предупреждения.
+    pub fn warnings(&self) -> &[Warning] {
+        return &self.warnings;
+    }
+}
+
+impl From<Response> for ValidationReport {
+    fn from(response: Response) -> Self {
+        return ValidationReport {
+            is_ok: response.errors.is_empty(),
+            errors: response.errors,
+            warnings: response.warnings,
+        };
+    }
 }
 
-/// Определяет разделитель, который будет использоваться при парсинге файла.
+/// Структура, описывающая статистику по результату парсинга.
 ///
-/// Если в начале файла есть строка `"@sep <разделитель>"`, то будет использован указанный разделитель.
-/// В противном случае будет использован разделитель, заданный в настройках по умолчанию.
+/// Структура содержит количество полей (`fields`), текстов (`texts`),
+/// текстов без перевода (`empty_translations`), строк с ошибками (`errors`),
+/// отсортированный набор всех встреченных тэгов (`tags`) и количество текстов
+/// с каждым тэгом (`tag_texts`).
+#[derive(Serialize, Default, Debug)]
+pub struct Stats {
+    pub fields: usize,
+    pub texts: usize,
+    pub empty_translations: usize,
+    pub errors: usize,
+    pub tags: BTreeSet<String>,
+    pub tag_texts: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    /// Возвращает не больше `count` тэгов, которые встречаются у наибольшего количества
+    /// текстов, вместе с этим количеством. Тэги с одинаковым количеством идут по алфавиту.
+    pub fn top_tags(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut top: Vec<(&str, usize)> = self
+            .tag_texts
+            .iter()
+            .map(|(tag, texts)| (tag.as_str(), *texts))
+            .collect();
+
+        top.sort_by_key(|(_, texts)| Reverse(*texts));
+        top.truncate(count);
+
+        return top;
+    }
+}
+
+/// Структура, описывающая настройки парсинга.
 ///
-fn get_separator(reader: &mut BufReader<&File>) -> String {
-    const DERECTIVE: &str = "@sep ";
+/// * `original_language` - идентификатор языка оригинала.
+/// * `translate_language` - идентификатор языка перевода.
+///   Директива `@lang` в начале файла имеет приоритет над обоими языками, а директива
+///   `@lang` после начала текста меняет языки только для следующих за ней полей.
+/// * `force_languages` - языки, которые используются для всего файла независимо
+///   от директив `@lang` (например, чтобы привести к одной паре языков разнородные файлы).
+///   Порядок приоритета: `force_languages`, затем директива `@lang`, затем
+///   `original_language` и `translate_language`, затем пустые языки по умолчанию.
+///   Директивы `@lang` при этом пропускаются, но с неверным количеством языков
+///   все равно дают предупреждение. По умолчанию не задано.
+/// * `keep_header_comments` - сохранять ли комментарии `//` из начала файла, до первой
+///   строки с текстом или тэгом, в [`Response`] (например, лицензию или авторство),
+///   чтобы [`crate::export::to_txt`] записал их обратно. Текст после `//` сохраняется
+///   как есть.
+/// * `fail_on_read_error` - прерывать ли парсинг с ошибкой [`ParseError::Io`], если строку
+///   не удалось прочитать (например, в ней неверный UTF-8). По умолчанию такая строка
+///   пропускается с предупреждением [`WarningKind::ReadError`]. Остальные ошибки
+///   ввода-вывода прерывают парсинг всегда, так как источник после них может так и не
+///   восстановиться. С возможностью `encoding` неверные байты заменяются при декодировании.
+/// * `warn_undefined_variables` - сообщать ли о `${ИМЯ}` в тексте для переменной, не заданной
+///   директивой `@define` (предупреждение [`WarningKind::UndefinedVariable`]). Такая
+///   переменная в любом случае остается в тексте как есть. По умолчанию выключено.
+/// * `line_continuation` - продолжать ли строку, которая заканчивается на `\`, следующей
+///   строкой файла (по умолчанию выключено). Обратная косая черта отбрасывается, пробелы
+///   перед ней сохраняются, а у следующей строки отбрасываются пробелы в начале.
+///   Так читаются длинные строки, перенесенные [`crate::export::to_txt_wrapped`],
+///   поэтому для чтения его результата настройку нужно включить.
+/// * `allow_include` - обрабатывать ли директивы `@include`. Директива читает файл
+///   с диска, поэтому по умолчанию выключено, и директива пропускается с предупреждением
+///   [`WarningKind::DisabledInclude`]. Включать стоит только для файлов из надежного источника.
+/// * `allow_include_outside` - разрешать ли в `@include` абсолютные пути и пути с `..`.
+///   По умолчанию такой путь дает ошибку [`ParseError::ForbiddenInclude`], и включаются
+///   только файлы из каталога включающего файла и его подкаталогов.
+/// * `alternatives_separator` - разделитель альтернативных переводов
+///   (например, `"|"` для строки `Hello -- Hallo | Guten Tag`). По умолчанию выключен.
+/// * `track_sep_offset` - сохранять ли в [`Text`] позицию разделителя в строке.
+/// * `normalize_typography` - заменять ли типографские кавычки и тире на ASCII
+///   (см. [`TYPOGRAPHY_TABLE`]).
+/// * `normalize_original_whitespace_only` - схлопывать ли повторяющиеся пробелы внутри
+///   оригинала. Перевод при этом остается без изменений.
+/// * `split_from` - по какому из разделителей в строке отделять перевод (см. [`SeparatorMode`]).
+/// * `fail_on_ambiguous_directive` - прерывать ли парсинг с ошибкой
+///   [`ParseError::AmbiguousDirective`] вместо предупреждения [`WarningKind::AmbiguousDirective`].
+/// * `keep_empty_fields` - добавлять ли в результат поля без текстов (блоки тэгов,
+///   в которых нет ни одной строки). Пустой набор тэгов без текстов не добавляется никогда.
+/// * `skip_line_predicate` - дополнительное условие пропуска строки (например, строк
+///   с метаданными). Вызывается для обрезанной строки до проверки на ошибки и тэги;
+///   если возвращает `true`, строка пропускается так же, как комментарий.
+/// * `allowed_chars_per_lang` - шаблоны допустимого символа для языков. Каждый символ
+///   оригинала и перевода проверяется по шаблону своего языка (например, `ru` ->
+///   `[\p{Cyrillic}\p{P}\s\d]`), о недопустимых символах сообщается
+///   предупреждением [`WarningKind::DisallowedCharacters`].
+/// * `normalize` - приводить ли оригинал и перевод к нормальной форме Unicode NFC
+///   (доступно с опцией `unicode-normalization`).
+/// * `detect_duplicates` - сообщать ли о текстах с одинаковым оригиналом внутри
+///   одного поля (предупреждение [`WarningKind::DuplicateOriginal`]).
+/// * `max_lines`, `max_bytes` - ограничения на количество строк и размер входных данных
+///   (вместе с переводами строк). При превышении парсинг прерывается с ошибкой
+///   [`ParseError::LimitExceeded`]. По умолчанию ограничений нет.
+/// * `separator` - разделитель оригинала и перевода, если в файле нет директивы `@sep`.
+///   Если не задан, используется [`DEFAULT_SEPARATOR`].
+/// * `auto_detect_separator` - определять ли разделитель по первым строкам файла
+///   (табуляция, `=` или `:`), если нет директивы `@sep` и не задан `separator`.
+///   Если определить разделитель однозначно не удалось, используется разделитель по умолчанию.
+///   Файл может сам запросить определение разделителя директивой `@sep auto`.
+/// * `tag_aliases` - синонимы тэгов (например, `btn` -> `button`). Тэги из директив
+///   заменяются по этой таблице и при добавлении, и при вычеркивании, поэтому синонимы
+///   попадают в одно поле. Тэги, которых нет в таблице, остаются без изменений.
+/// * `case_insensitive_tags` - приводить ли тэги к нижнему регистру (после замены
+///   синонимов), чтобы `#Button` и `#button` попадали в одно поле. По умолчанию выключено.
+/// * `encoding` - кодировка входных данных (например, `windows-1251`), из которой
+///   текст перекодируется в UTF-8. Директива `@charset` в первой строке файла имеет
+///   приоритет. По умолчанию UTF-8 (доступно с опцией `encoding`).
+/// * `trim` - обрезать ли пробелы по краям оригинала и перевода (по умолчанию включено).
+///   Если выключено, у строки отбрасывается только перевод строки, а пробелы по краям
+///   и вокруг разделителя остаются в тексте.
+/// * `validate_languages` - проверять ли языки (из настроек или директивы `@lang`).
+///   Код должен иметь вид тэга BCP 47 `язык[-письменность][-регион][-вариант]...`
+///   (например, `pt-BR` или `zh-Hans`), где язык входит в список кодов ISO 639-1
+///   (см. [`ISO_639_1`]) без учета регистра. Для неизвестного или неверно записанного
+///   кода возвращается [`ParseError::UnknownLanguage`]. Пустой язык не проверяется.
+///   Язык в любом случае хранится так, как он записан, вместе с регионом и письменностью.
+/// * `inline_comment` - маркер комментария в конце строки (по умолчанию `//`): все,
+///   начиная с маркера, отбрасывается до разбора строки. Маркер внутри кавычек
+///   или экранированный (`\//`) остается в тексте. `None` отключает такие комментарии.
+/// * `tag_prefix`, `bulk_tag_prefix` - префиксы директив одного тэга (по умолчанию `#`)
+///   и нескольких тэгов (по умолчанию `@tags`). Закрывающая директива получается
+///   удвоением первого символа: например, `$scope` открывает тэг, а `$$scope` закрывает.
+///   Пустой префикс заменяется префиксом по умолчанию.
+/// * `max_active_tags` - наибольшее количество одновременно активных тэгов. Директива,
+///   после которой тэгов стало бы больше, пропускается с предупреждением
+///   [`WarningKind::TooManyTags`]. По умолчанию ограничения нет.
+/// * `fail_on_too_many_tags` - прерывать ли парсинг с ошибкой [`ParseError::TooManyTags`]
+///   вместо предупреждения.
+/// * `warn_unclosed_tags` - сообщать ли о тэгах, которые остались открытыми в конце файла
+///   (предупреждение [`WarningKind::UnclosedTag`] со строкой, где тэг был открыт).
+/// * `fail_fast` - прерывать ли парсинг с ошибкой [`ParseError::InvalidContent`]
+///   на первой строке с запрещенными символами вместо того, чтобы собирать все такие
+///   строки в [`Response`].
+/// * `keep_raw` - сохранять ли в каждом тексте исходную строку файла (`raw`) в том виде,
+///   в каком она была прочитана: до обрезки пробелов, удаления комментария и разбиения.
+/// * `forbidden_chars` - символы, которые не должны встречаться в тексте (по умолчанию
+///   [`DEFAULT_FORBIDDEN_CHARS`]). Строка с любым из них попадает в ошибки.
+///   Пустая строка отключает проверку.
+/// * `max_line_len` - наибольшая длина строки файла в байтах. Более длинная строка
+///   пропускается целиком с предупреждением [`WarningKind::LineTooLong`] и в память
+///   не читается. По умолчанию ограничения нет.
+/// * `sort_content` - сортировать ли тексты внутри каждого поля по оригиналу без учета
+///   регистра. Тексты с одинаковым оригиналом остаются в порядке файла.
+///   По умолчанию тексты идут в порядке файла.
+/// * `collect_directives` - записывать ли в [`Response`] все распознанные директивы
+///   с номерами строк (см. [`DirectiveInfo`]), например для подсказок в редакторе.
+/// * `strict_directives` - прерывать ли парсинг с ошибкой [`ParseError::UnknownDirective`]
+///   на строке, которая начинается с `@`, но не является известной директивой
+///   (например, `@seperator`). Известная директива, записанная с ошибкой (например,
+///   `@comment` без заметки), приводит к ошибке [`ParseError::InvalidDirective`].
+///   По умолчанию такая строка считается текстом.
+/// * `dedup_content` - убирать ли из каждого поля повторы текстов с тем же оригиналом
+///   и переводами (например, если один и тот же блок тэгов повторяется в файле).
+///   Остается первое вхождение, порядок текстов сохраняется.
+/// * `errors_as_warnings` - разбирать ли строку с запрещенными символами как обычную строку.
+///   Ошибка о ней все равно записывается в [`Response`], но текст из строки попадает
+///   и в поле. Не действует вместе с `fail_fast`: тогда парсинг прерывается, как обычно.
+/// * `max_errors` - наибольшее количество ошибок в [`Response`]. Следующие строки
+///   с запрещенными символами пропускаются, как обычно, но в ошибки не записываются,
+///   а в результате отмечается `errors_truncated`. По умолчанию ограничения нет.
+#[non_exhaustive]
+pub struct ParseOptions {
+    pub original_language: String,
+    pub translate_language: String,
+    pub alternatives_separator: Option<String>,
+    pub track_sep_offset: bool,
+    pub normalize_typography: bool,
+    pub normalize_original_whitespace_only: bool,
+    pub split_from: SeparatorMode,
+    pub fail_on_ambiguous_directive: bool,
+    pub keep_empty_fields: bool,
+    pub skip_line_predicate: Option<LinePredicate>,
+    pub allowed_chars_per_lang: HashMap<String, Regex>,
+    #[cfg(feature = "unicode-normalization")]
+    pub normalize: bool,
+    pub detect_duplicates: bool,
+    pub max_lines: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub separator: Option<String>,
+    pub auto_detect_separator: bool,
+    pub tag_aliases: HashMap<String, String>,
+    pub case_insensitive_tags: bool,
+    #[cfg(feature = "encoding")]
+    pub encoding: Option<String>,
+    pub trim: bool,
+    pub validate_languages: bool,
+    pub inline_comment: Option<String>,
+    pub tag_prefix: String,
+    pub bulk_tag_prefix: String,
+    pub max_active_tags: Option<usize>,
+    pub fail_on_too_many_tags: bool,
+    pub warn_unclosed_tags: bool,
+    pub fail_fast: bool,
+    pub keep_raw: bool,
+    pub forbidden_chars: String,
+    pub max_line_len: Option<usize>,
+    pub sort_content: bool,
+    pub collect_directives: bool,
+    pub strict_directives: bool,
+    pub dedup_content: bool,
+    pub errors_as_warnings: bool,
+    pub max_errors: Option<usize>,
+    pub force_languages: Option<Languages>,
+    pub keep_header_comments: bool,
+    pub fail_on_read_error: bool,
+    pub warn_undefined_variables: bool,
+    pub line_continuation: bool,
+    pub allow_include: bool,
+    pub allow_include_outside: bool,
+}
 
-    for line in reader.lines() {
-        let string = line.unwrap().trim().to_string();
+impl Default for ParseOptions {
+    fn default() -> Self {
+        return ParseOptions {
+            original_language: Default::default(),
+            translate_language: Default::default(),
+            alternatives_separator: None,
+            track_sep_offset: false,
+            normalize_typography: false,
+            normalize_original_whitespace_only: false,
+            split_from: Default::default(),
+            fail_on_ambiguous_directive: false,
+            keep_empty_fields: false,
+            skip_line_predicate: None,
+            allowed_chars_per_lang: Default::default(),
+            #[cfg(feature = "unicode-normalization")]
+            normalize: false,
+            detect_duplicates: false,
+            max_lines: None,
+            max_bytes: None,
+            separator: None,
+            auto_detect_separator: false,
+            tag_aliases: Default::default(),
+            case_insensitive_tags: false,
+            #[cfg(feature = "encoding")]
+            encoding: None,
+            trim: true,
+            validate_languages: false,
+            inline_comment: Some("//".to_string()),
+            tag_prefix: DEFAULT_TAG_PREFIX.to_string(),
+            bulk_tag_prefix: DEFAULT_BULK_TAG_PREFIX.to_string(),
+            max_active_tags: None,
+            fail_on_too_many_tags: false,
+            warn_unclosed_tags: false,
+            fail_fast: false,
+            keep_raw: false,
+            forbidden_chars: DEFAULT_FORBIDDEN_CHARS.to_string(),
+            max_line_len: None,
+            sort_content: false,
+            collect_directives: false,
+            strict_directives: false,
+            dedup_content: false,
+            errors_as_warnings: false,
+            max_errors: None,
+            force_languages: None,
+            keep_header_comments: false,
+            fail_on_read_error: false,
+            warn_undefined_variables: false,
+            line_continuation: false,
+            allow_include: false,
+            allow_include_outside: false,
+        };
+    }
+}
 
-        if string.starts_with(DERECTIVE) {
-            return string.replace(DERECTIVE, "").trim().to_string();
-        } else if !string.is_empty() && !string.starts_with("//") {
-            reader.seek(SeekFrom::Start(0)).unwrap();
-            break;
-        }
+impl ParseOptions {
+    /// Создает построитель настроек со значениями по умолчанию.
+    ///
+    /// Структура помечена `#[non_exhaustive]`, поэтому вне крейта настройки
+    /// создаются через построитель (или [`Default`] с последующим изменением полей),
+    /// и новые настройки не ломают существующий код.
+    pub fn builder() -> ParseOptionsBuilder {
+        return ParseOptionsBuilder::default();
+    }
+}
+
+/// Построитель [`ParseOptions`] (см. [`ParseOptions::builder`]).
+///
+/// Каждый метод задает одну настройку, значения остальных остаются по умолчанию.
+#[derive(Default)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    /// Задает [`ParseOptions::original_language`].
+    pub fn original_language(mut self, value: impl Into<String>) -> Self {
+        self.options.original_language = value.into();
+        return self;
+    }
+
+    /// Задает [`ParseOptions::translate_language`].
+    pub fn translate_language(mut self, value: impl Into<String>) -> Self {
+        self.options.translate_language = value.into();
+        return self;
+    }
+
+    /// Задает [`ParseOptions::alternatives_separator`].
+    pub fn alternatives_separator(mut self, value: impl Into<String>) -> Self {
+        self.options.alternatives_separator = Some(value.into());
+        return self;
+    }
+
+    /// Задает [`ParseOptions::track_sep_offset`].
+    pub fn track_sep_offset(mut self, value: bool) -> Self {
+        self.options.track_sep_offset = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::normalize_typography`].
+    pub fn normalize_typography(mut self, value: bool) -> Self {
+        self.options.normalize_typography = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::normalize_original_whitespace_only`].
+    pub fn normalize_original_whitespace_only(mut self, value: bool) -> Self {
+        self.options.normalize_original_whitespace_only = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::split_from`].
+    pub fn split_from(mut self, value: SeparatorMode) -> Self {
+        self.options.split_from = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::fail_on_ambiguous_directive`].
+    pub fn fail_on_ambiguous_directive(mut self, value: bool) -> Self {
+        self.options.fail_on_ambiguous_directive = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::keep_empty_fields`].
+    pub fn keep_empty_fields(mut self, value: bool) -> Self {
+        self.options.keep_empty_fields = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::skip_line_predicate`].
+    pub fn skip_line_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.options.skip_line_predicate = Some(Box::new(predicate));
+        return self;
+    }
+
+    /// Добавляет шаблон допустимого символа для языка (см. [`ParseOptions::allowed_chars_per_lang`]).
+    pub fn allowed_chars(mut self, language: impl Into<String>, pattern: Regex) -> Self {
+        self.options
+            .allowed_chars_per_lang
+            .insert(language.into(), pattern);
+        return self;
+    }
+
+    /// Задает [`ParseOptions::normalize`].
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalize(mut self, value: bool) -> Self {
+        self.options.normalize = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::detect_duplicates`].
+    pub fn detect_duplicates(mut self, value: bool) -> Self {
+        self.options.detect_duplicates = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::max_lines`].
+    pub fn max_lines(mut self, value: usize) -> Self {
+        self.options.max_lines = Some(value);
+        return self;
+    }
+
+    /// Задает [`ParseOptions::max_bytes`].
+    pub fn max_bytes(mut self, value: usize) -> Self {
+        self.options.max_bytes = Some(value);
+        return self;
     }
 
-    return dotenv!("DEFAULT_SEPARATOR").to_string();
+    /// Задает [`ParseOptions::separator`].
+    pub fn separator(mut self, value: impl Into<String>) -> Self {
+        self.options.separator = Some(value.into());
+        return self;
+    }
+
+    /// Задает [`ParseOptions::auto_detect_separator`].
+    pub fn auto_detect_separator(mut self, value: bool) -> Self {
+        self.options.auto_detect_separator = value;
+        return self;
+    }
+
+    /// Добавляет синоним тэга (см. [`ParseOptions::tag_aliases`]).
+    pub fn tag_alias(mut self, alias: impl Into<String>, tag: impl Into<String>) -> Self {
+        self.options.tag_aliases.insert(alias.into(), tag.into());
+        return self;
+    }
+
+    /// Задает [`ParseOptions::case_insensitive_tags`].
+    pub fn case_insensitive_tags(mut self, value: bool) -> Self {
+        self.options.case_insensitive_tags = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::encoding`].
+    #[cfg(feature = "encoding")]
+    pub fn encoding(mut self, value: impl Into<String>) -> Self {
+        self.options.encoding = Some(value.into());
+        return self;
+    }
+
+    /// Задает [`ParseOptions::trim`].
+    pub fn trim(mut self, value: bool) -> Self {
+        self.options.trim = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::validate_languages`].
+    pub fn validate_languages(mut self, value: bool) -> Self {
+        self.options.validate_languages = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::inline_comment`]; `None` отключает комментарии в конце строки.
+    pub fn inline_comment(mut self, marker: Option<&str>) -> Self {
+        self.options.inline_comment = marker.map(|x| x.to_string());
+        return self;
+    }
+
+    /// Задает [`ParseOptions::tag_prefix`].
+    pub fn tag_prefix(mut self, value: impl Into<String>) -> Self {
+        self.options.tag_prefix = value.into();
+        return self;
+    }
+
+    /// Задает [`ParseOptions::bulk_tag_prefix`].
+    pub fn bulk_tag_prefix(mut self, value: impl Into<String>) -> Self {
+        self.options.bulk_tag_prefix = value.into();
+        return self;
+    }
+
+    /// Задает [`ParseOptions::max_active_tags`].
+    pub fn max_active_tags(mut self, value: usize) -> Self {
+        self.options.max_active_tags = Some(value);
+        return self;
+    }
+
+    /// Задает [`ParseOptions::fail_on_too_many_tags`].
+    pub fn fail_on_too_many_tags(mut self, value: bool) -> Self {
+        self.options.fail_on_too_many_tags = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::warn_unclosed_tags`].
+    pub fn warn_unclosed_tags(mut self, value: bool) -> Self {
+        self.options.warn_unclosed_tags = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::fail_fast`].
+    pub fn fail_fast(mut self, value: bool) -> Self {
+        self.options.fail_fast = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::keep_raw`].
+    pub fn keep_raw(mut self, value: bool) -> Self {
+        self.options.keep_raw = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::forbidden_chars`].
+    pub fn forbidden_chars(mut self, value: impl Into<String>) -> Self {
+        self.options.forbidden_chars = value.into();
+        return self;
+    }
+
+    /// Задает [`ParseOptions::max_line_len`].
+    pub fn max_line_len(mut self, value: usize) -> Self {
+        self.options.max_line_len = Some(value);
+        return self;
+    }
+
+    /// Задает [`ParseOptions::sort_content`].
+    pub fn sort_content(mut self, value: bool) -> Self {
+        self.options.sort_content = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::collect_directives`].
+    pub fn collect_directives(mut self, value: bool) -> Self {
+        self.options.collect_directives = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::strict_directives`].
+    pub fn strict_directives(mut self, value: bool) -> Self {
+        self.options.strict_directives = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::dedup_content`].
+    pub fn dedup_content(mut self, value: bool) -> Self {
+        self.options.dedup_content = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::errors_as_warnings`].
+    pub fn errors_as_warnings(mut self, value: bool) -> Self {
+        self.options.errors_as_warnings = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::max_errors`].
+    pub fn max_errors(mut self, value: usize) -> Self {
+        self.options.max_errors = Some(value);
+        return self;
+    }
+
+    /// Задает [`ParseOptions::force_languages`].
+    pub fn force_languages(mut self, value: Languages) -> Self {
+        self.options.force_languages = Some(value);
+        return self;
+    }
+
+    /// Задает [`ParseOptions::keep_header_comments`].
+    pub fn keep_header_comments(mut self, value: bool) -> Self {
+        self.options.keep_header_comments = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::fail_on_read_error`].
+    pub fn fail_on_read_error(mut self, value: bool) -> Self {
+        self.options.fail_on_read_error = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::warn_undefined_variables`].
+    pub fn warn_undefined_variables(mut self, value: bool) -> Self {
+        self.options.warn_undefined_variables = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::line_continuation`].
+    pub fn line_continuation(mut self, value: bool) -> Self {
+        self.options.line_continuation = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::allow_include`].
+    pub fn allow_include(mut self, value: bool) -> Self {
+        self.options.allow_include = value;
+        return self;
+    }
+
+    /// Задает [`ParseOptions::allow_include_outside`].
+    pub fn allow_include_outside(mut self, value: bool) -> Self {
+        self.options.allow_include_outside = value;
+        return self;
+    }
+
+    /// Возвращает готовые настройки.
+    pub fn build(self) -> ParseOptions {
+        return self.options;
+    }
+}
+
+/// Перечисление, описывающее, по какому из разделителей разбивается строка,
+/// если в ней их несколько.
+///
+/// * `First` - по первому (поведение по умолчанию).
+/// * `Last` - по последнему. Все предыдущие разделители остаются в оригинале,
+///   а сама строка отмечается предупреждением [`WarningKind::AmbiguousSeparator`].
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SeparatorMode {
+    #[default]
+    First,
+    Last,
+}
+
+/// Разделитель оригинала и перевода, который используется, если в файле нет директивы `@sep`
+/// и он не задан в [`ParseOptions::separator`].
+pub const DEFAULT_SEPARATOR: &str = "--";
+
+/// Коды языков ISO 639-1 в нижнем регистре, по которым проверяются языки
+/// при [`ParseOptions::validate_languages`].
+pub const ISO_639_1: &[&str] = &[
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg", "bi",
+    "bm", "bn", "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy", "da", "de",
+    "dv", "dz", "ee", "el", "en", "eo", "es", "et", "eu", "fa", "ff", "fi", "fj", "fo", "fr", "fy",
+    "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he", "hi", "ho", "hr", "ht", "hu", "hy", "hz", "ia",
+    "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv", "ka", "kg", "ki", "kj", "kk",
+    "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky", "la", "lb", "lg", "li", "ln", "lo",
+    "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn", "mr", "ms", "mt", "my", "na", "nb", "nd",
+    "ne", "ng", "nl", "nn", "no", "nr", "nv", "ny", "oc", "oj", "om", "or", "os", "pa", "pi", "pl",
+    "ps", "pt", "qu", "rm", "rn", "ro", "ru", "rw", "sa", "sc", "sd", "se", "sg", "si", "sk", "sl",
+    "sm", "sn", "so", "sq", "sr", "ss", "st", "su", "sv", "sw", "ta", "te", "tg", "th", "ti", "tk",
+    "tl", "tn", "to", "tr", "ts", "tt", "tw", "ty", "ug", "uk", "ur", "uz", "ve", "vi", "vo", "wa",
+    "wo", "xh", "yi", "yo", "za", "zh", "zu",
+];
+
+/// Префикс директивы одного тэга по умолчанию (см. [`ParseOptions::tag_prefix`]).
+pub const DEFAULT_TAG_PREFIX: &str = "#";
+
+/// Префикс директивы нескольких тэгов по умолчанию (см. [`ParseOptions::bulk_tag_prefix`]).
+pub const DEFAULT_BULK_TAG_PREFIX: &str = "@tags";
+
+/// Директива, с которой начинается блок строк без разделителя.
+const RAW_OPEN: &str = "@raw";
+
+/// Директива, которой заканчивается блок строк без разделителя.
+const RAW_CLOSE: &str = "@@raw";
+
+/// Директивы, которые распознает парсер, кроме директив тэгов (см. [`TagSyntax`]).
+const DIRECTIVES: [&str; 8] = [
+    "@sep", "@charset", "@lang", "@comment", "@include", "@define", RAW_OPEN, RAW_CLOSE,
+];
+
+/// Начало переменной `@define`, которое не раскрывается (см. [`parse_reader`]).
+const ESCAPED_VARIABLE: &str = "\\${";
+
+/// Символы, запрещенные в тексте по умолчанию (см. [`ParseOptions::forbidden_chars`]).
+pub const DEFAULT_FORBIDDEN_CHARS: &str = "<>:\"/\\|*";
+
+/// Условие, по которому строка пропускается при парсинге (см. [`ParseOptions::skip_line_predicate`]).
+pub type LinePredicate = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Таблица замен, используемая при [`ParseOptions::normalize_typography`].
+///
+/// | Символ | Название           | Замена |
+/// |--------|--------------------|--------|
+/// | `“`    | левая кавычка      | `"`    |
+/// | `”`    | правая кавычка     | `"`    |
+/// | `‘`    | левый апостроф     | `'`    |
+/// | `’`    | правый апостроф    | `'`    |
+/// | `–`    | короткое тире      | `-`    |
+/// | `—`    | длинное тире       | `--`   |
+pub const TYPOGRAPHY_TABLE: &[(char, &str)] = &[
+    ('\u{201C}', "\""),
+    ('\u{201D}', "\""),
+    ('\u{2018}', "'"),
+    ('\u{2019}', "'"),
+    ('\u{2013}', "-"),
+    ('\u{2014}', "--"),
+];
+
+/// Структура, описывающая отдельный текст для перевода.
+///
+/// Структура содержит оригинальный текст (`original`) и его перевод (`translate`).
+/// Если в настройках задан [`ParseOptions::alternatives_separator`], то все варианты
+/// перевода попадают в `translates`, а в `translate` остается первый из них.
+///
+/// Если включен [`ParseOptions::track_sep_offset`], то в `sep_offset` хранится
+/// номер символа (не байта) исходной строки, с которого начинается разделитель.
+///
+/// В `line` хранится номер строки файла (начиная с 1), из которой прочитан текст.
+///
+/// Если включен [`ParseOptions::keep_raw`], то в `raw` хранится исходная строка
+/// без изменений (без перевода строки).
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Text {
+    pub original: String,
+    pub translate: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub translates: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sep_offset: Option<usize>,
+    #[serde(default)]
+    pub(crate) line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) raw: Option<String>,
+}
+
+/// Структура, описывающая поле в файле.
+///
+/// Структура содержит набор тегов (`tags`), с помощью которых
+/// поле можно идентифицировать, и вектор текстов для перевода (`content`).
+/// Тэги сериализуются в отсортированном порядке, чтобы результат не менялся от запуска к запуску.
+/// Заметки из директив `@comment` хранятся в `comments` и в JSON попадают, только если они есть.
+/// Если языки поля заданы директивой `@lang` посреди файла и отличаются от языков файла,
+/// они хранятся в `languages`;
+/// у остальных полей `languages` нет, и для них действуют языки всего файла.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Field {
+    #[serde(serialize_with = "serialize_sorted")]
+    pub(crate) tags: HashSet<Tag>,
+    pub(crate) content: Vec<Text>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) comments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) languages: Option<Languages>,
+}
+
+impl Field {
+    /// Возвращает тэги поля.
+    pub fn tags(&self) -> &HashSet<Tag> {
+        return &self.tags;
+    }
+
+    /// Возвращает тексты поля в порядке их появления в файле.
+    pub fn content(&self) -> &[Text] {
+        return &self.content;
+    }
+
+    /// Возвращает заметки поля из директив `@comment`.
+    pub fn comments(&self) -> &[String] {
+        return &self.comments;
+    }
+
+    /// Возвращает языки поля, если они заданы директивой `@lang` посреди файла.
+    pub fn languages(&self) -> Option<&Languages> {
+        return self.languages.as_ref();
+    }
+
+    /// Возвращает количество текстов в поле.
+    pub fn content_len(&self) -> usize {
+        return self.content.len();
+    }
+}
+
+/// Сериализует набор тэгов в виде отсортированного массива строк.
+fn serialize_sorted<S: Serializer>(set: &HashSet<Tag>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut sorted: Vec<&Tag> = set.iter().collect();
+    sorted.sort();
+
+    return sorted.serialize(serializer);
+}
+
+/// Тэг поля.
+///
+/// Имя тэга не бывает пустым и не содержит пробелов по краям: это проверяет [`Tag::new`].
+/// Синонимы и регистр (см. [`ParseOptions::tag_aliases`] и
+/// [`ParseOptions::case_insensitive_tags`]) учитываются при чтении файла.
+/// В JSON тэг записывается обычной строкой.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[serde(transparent)]
+pub struct Tag(String);
+
+impl Tag {
+    /// Создает тэг из имени без пробелов по краям. Для пустого имени возвращает [`None`].
+    pub fn new(name: &str) -> Option<Tag> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return None;
+        }
+
+        return Some(Tag(name.to_string()));
+    }
+
+    /// Возвращает имя тэга.
+    pub fn as_str(&self) -> &str {
+        return &self.0;
+    }
+
+    /// Приводит тэг к каноническому виду: заменяет синоним из [`ParseOptions::tag_aliases`]
+    /// и, при [`ParseOptions::case_insensitive_tags`], переводит в нижний регистр.
+    fn canonical(self, options: &ParseOptions) -> Tag {
+        let tag = match options.tag_aliases.get(self.as_str()) {
+            Some(alias) => Tag::new(alias).unwrap_or(self),
+            None => self,
+        };
+
+        return match options.case_insensitive_tags {
+            true => Tag(tag.0.to_lowercase()),
+            false => tag,
+        };
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(&self.0);
+    }
+}
+
+impl Borrow<str> for Tag {
+    fn borrow(&self) -> &str {
+        return &self.0;
+    }
+}
+
+/// Структура, описывающая языки, используемые в файле для перевода.
+///
+/// Структура содержит идентификатор языка оригинала (`original`) и идентификатор языка перевода (`translate`).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Languages {
+    pub(crate) original: String,
+    pub(crate) translate: String,
+}
+
+impl Languages {
+    /// Создает пару языков оригинала и перевода.
+    pub fn new(original: impl Into<String>, translate: impl Into<String>) -> Languages {
+        return Languages {
+            original: original.into(),
+            translate: translate.into(),
+        };
+    }
+
+    /// Возвращает идентификатор языка оригинала.
+    pub fn original(&self) -> &str {
+        return &self.original;
+    }
+
+    /// Возвращает идентификатор языка перевода.
+    pub fn translate(&self) -> &str {
+        return &self.translate;
+    }
+}
+
+/// Структура, описывающая строку с ошибкой при парсинге файла.
+///
+/// Структура содержит положение ошибки (`location`, см. [`Location`]) - номер строки
+/// и номер символа строки файла, с которого начинается первая ошибка, - вектор индексов
+/// столбцов (`columns`, номера байтов), в которых были найдены ошибки, а также саму строку
+/// с ошибкой без пробелов по краям (`string`). В `spans` для каждой ошибки хранится
+/// диапазон символов (не байтов) `string`, что удобно для редакторов (см. [`Span`]).
+///
+/// В `file` указывается файл, в котором найдена ошибка. Поле заполняется только
+/// при парсинге по пути ([`parse`], [`parse_dir`]); для [`parse_str`] и [`parse_reader`] оно пустое.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorLine {
+    #[serde(flatten)]
+    pub(crate) location: Location,
+    pub(crate) columns: Vec<usize>,
+    #[serde(default)]
+    pub(crate) spans: Vec<Span>,
+    pub(crate) string: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) file: Option<String>,
+}
+
+impl ErrorLine {
+    /// Возвращает положение первой ошибки в строке.
+    pub fn location(&self) -> Location {
+        return self.location;
+    }
+
+    /// Возвращает номера байтов строки, в которых найдены ошибки.
+    pub fn columns(&self) -> &[usize] {
+        return &self.columns;
+    }
+
+    /// Возвращает диапазоны символов строки с ошибками.
+    pub fn spans(&self) -> &[Span] {
+        return &self.spans;
+    }
+
+    /// Возвращает строку с ошибкой без пробелов по краям.
+    pub fn string(&self) -> &str {
+        return &self.string;
+    }
+
+    /// Возвращает файл, в котором найдена ошибка, если он известен.
+    pub fn file(&self) -> Option<&str> {
+        return self.file.as_deref();
+    }
+}
+
+/// Структура, описывающая диапазон символов строки с ошибкой.
+///
+/// `start_char` - номер первого символа, `end_char` - номер символа после последнего.
+/// Символы считаются с нуля, как [`char`], а не как байты.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Span {
+    pub start_char: usize,
+    pub end_char: usize,
+}
+
+/// Структура, описывающая предупреждение, найденное при парсинге файла.
+///
+/// В отличие от [`ErrorLine`], строка с предупреждением все равно попадает в результат.
+/// Структура содержит положение (`location`, см. [`Location`]), вид предупреждения (`kind`),
+/// его описание (`message`) и, как и [`ErrorLine`], файл (`file`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Warning {
+    #[serde(flatten)]
+    pub(crate) location: Location,
+    pub(crate) kind: WarningKind,
+    pub(crate) message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) file: Option<String>,
+}
+
+impl Warning {
+    /// Возвращает положение предупреждения.
+    pub fn location(&self) -> Location {
+        return self.location;
+    }
+
+    /// Возвращает вид предупреждения.
+    pub fn kind(&self) -> WarningKind {
+        return self.kind;
+    }
+
+    /// Возвращает описание предупреждения.
+    pub fn message(&self) -> &str {
+        return &self.message;
+    }
+
+    /// Возвращает файл, к которому относится предупреждение, если он известен.
+    pub fn file(&self) -> Option<&str> {
+        return self.file.as_deref();
+    }
+}
+
+/// Структура, описывающая директиву, найденную в файле (см. [`ParseOptions::collect_directives`]).
+///
+/// Структура содержит вид директивы (`kind`), ее положение (`location`) и саму строку
+/// без пробелов по краям (`raw`). Для списка `@tags`, продолжающегося на нескольких
+/// строках, записывается первая строка и весь список целиком.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DirectiveInfo {
+    pub kind: DirectiveKind,
+    #[serde(flatten)]
+    pub location: Location,
+    pub raw: String,
+}
+
+/// Структура, описывающая положение в файле для ошибок, предупреждений и директив.
+///
+/// `line` - номер строки, начиная с 1 (`0`, если положение не относится к строке файла,
+/// например, для предупреждений [`Response::merge`]). `column` - номер символа в строке,
+/// начиная с 1, если он известен. Символы считаются как [`char`], а не как байты.
+///
+/// В JSON поля записываются рядом с остальными полями диагностики: `"line"` и `"column"`?.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug,
+)]
+pub struct Location {
+    pub line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+}
+
+impl Location {
+    /// Создает положение в строке `line` без номера символа.
+    pub fn new(line: u32) -> Location {
+        return Location { line, column: None };
+    }
+
+    /// Возвращает положение с номером символа `column`.
+    pub fn with_column(self, column: u32) -> Location {
+        return Location {
+            column: Some(column),
+            ..self
+        };
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self.column {
+            Some(column) => write!(f, "{}:{column}", self.line),
+            None => write!(f, "{}", self.line),
+        };
+    }
+}
+
+/// Перечисление, описывающее виды директив.
+///
+/// * `Separator` - `@sep`.
+/// * `Charset` - `@charset`.
+/// * `Language` - `@lang`.
+/// * `Tags` - открывающие и закрывающие директивы тэгов (`#`, `##`, `@tags`, `@@tags`).
+/// * `Comment` - `@comment`.
+/// * `Include` - `@include`.
+/// * `Raw` - начало и конец блока без разделителей (`@raw`, `@@raw`).
+/// * `Define` - `@define`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectiveKind {
+    Separator,
+    Charset,
+    Language,
+    Tags,
+    Comment,
+    Include,
+    Raw,
+    Define,
+}
+
+/// Перечисление, описывающее виды предупреждений.
+///
+/// * `AmbiguousSeparator` - в режиме [`SeparatorMode::Last`] в строке больше одного
+///   неэкранированного разделителя.
+/// * `AmbiguousDirective` - строка с тэгами одновременно похожа на открывающую
+///   и закрывающую директиву.
+/// * `DisallowedCharacters` - в тексте есть символы, не подходящие под шаблон
+///   [`ParseOptions::allowed_chars_per_lang`] для его языка.
+/// * `InvalidDirective` - директива записана с ошибкой и была пропущена.
+/// * `DuplicateOriginal` - в одном поле несколько текстов с одинаковым оригиналом.
+/// * `MisplacedDirective` - директива стоит там, где она не действует, и была пропущена.
+/// * `TooManyTags` - директива открыла бы больше тэгов, чем разрешено
+///   [`ParseOptions::max_active_tags`], и была пропущена.
+/// * `UnclosedTag` - тэг остался открытым в конце файла (при [`ParseOptions::warn_unclosed_tags`]).
+/// * `LineTooLong` - строка длиннее [`ParseOptions::max_line_len`] и пропущена.
+/// * `ReadError` - строка в неверном UTF-8 пропущена (см. [`ParseOptions::fail_on_read_error`]).
+/// * `UndefinedVariable` - в тексте есть `${ИМЯ}` для переменной, не заданной `@define`
+///   (при [`ParseOptions::warn_undefined_variables`]).
+/// * `DisabledInclude` - директива `@include` пропущена, так как включение файлов
+///   выключено (см. [`ParseOptions::allow_include`]).
+/// * `LanguageMismatch` - при объединении результатов (см. [`Response::merge`]) языки
+///   не совпали; сохраняются языки результата, в который выполняется объединение.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    AmbiguousSeparator,
+    AmbiguousDirective,
+    DisallowedCharacters,
+    InvalidDirective,
+    DuplicateOriginal,
+    MisplacedDirective,
+    LanguageMismatch,
+    TooManyTags,
+    UnclosedTag,
+    LineTooLong,
+    ReadError,
+    UndefinedVariable,
+    DisabledInclude,
+}
+
+/// Описывает функцию, которая парсит файл и создает объект-ответ.
+///
+/// * `path_to_file: &`[`Path`] - путь до файла, который нужно парсить.
+/// * `options: &`[`ParseOptions`] - настройки парсинга.
+///
+/// Функция возвращает `Result<Box<Response>, ParseError>`, где [`Ok`] - успешно
+/// пропарсенный объект-ответ, а [`Err`] - ошибка при чтении или парсинге файла.
+///
+/// С опцией `gzip` сжатые файлы (с расширением `.gz` или сигнатурой gzip)
+/// распаковываются при чтении.
+///
+/// Файл открывается один раз и читается за один проход без перемещения назад
+/// (см. [`parse_reader`]), поэтому результат согласован, даже если файл дописывается
+/// во время парсинга или является именованным каналом.
+pub fn parse(path_to_file: &Path, options: &ParseOptions) -> Result<Box<Response>, ParseError> {
+    let mut context = IncludeContext::default();
+
+    return parse_file(
+        path_to_file,
+        &path_to_file.display().to_string(),
+        options,
+        &mut context,
+        None,
+    );
+}
+
+/// Парсит файл так же, как [`parse`], и сообщает о ходе парсинга.
+///
+/// * `interval: `[`usize`] - через сколько строк вызывать `progress` (минимум 1).
+/// * `progress` - функция, которой передается количество уже обработанных строк файла.
+///   Строки файлов из директив `@include` не учитываются.
+pub fn parse_with_progress<F: FnMut(usize)>(
+    path_to_file: &Path,
+    options: &ParseOptions,
+    interval: usize,
+    mut progress: F,
+) -> Result<Box<Response>, ParseError> {
+    let mut context = IncludeContext::default();
+    let progress = Progress {
+        interval: interval.max(1),
+        callback: &mut progress,
+    };
+
+    return parse_file(
+        path_to_file,
+        &path_to_file.display().to_string(),
+        options,
+        &mut context,
+        Some(progress),
+    );
+}
+
+/// Функция, которой сообщается о ходе парсинга (см. [`parse_with_progress`]),
+/// и интервал в строках между ее вызовами.
+struct Progress<'a> {
+    interval: usize,
+    callback: &'a mut dyn FnMut(usize),
+}
+
+/// Файлы, которые разбираются в данный момент, от корневого до текущего.
+///
+/// Используется директивой `@include`: относительные пути считаются от каталога
+/// последнего файла в цепочке, а повторное появление файла в цепочке означает цикл.
+#[derive(Default)]
+struct IncludeContext {
+    chain: Vec<PathBuf>,
+    /// Результат будет объединен с другими (в [`parse_dir`] или во включающем файле),
+    /// поэтому тексты обрабатываются (см. [`process_content`]) уже после объединения.
+    merged: bool,
+}
+
+impl IncludeContext {
+    /// Каталог, от которого отсчитываются пути в `@include`. Для текста, который
+    /// не читается из файла, это текущий рабочий каталог.
+    fn base_dir(&self) -> &Path {
+        return match self.chain.last().and_then(|path| path.parent()) {
+            Some(dir) => dir,
+            None => Path::new(""),
+        };
+    }
+}
+
+/// Парсит файл в рамках цепочки включений `context`.
+///
+/// У ошибок и предупреждений, для которых файл еще не указан, в качестве файла
+/// записывается `name`.
+fn parse_file(
+    path: &Path,
+    name: &str,
+    options: &ParseOptions,
+    context: &mut IncludeContext,
+    progress: Option<Progress>,
+) -> Result<Box<Response>, ParseError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse", file = name).entered();
+
+    let canonical = fs::canonicalize(path)?;
+
+    if context.chain.contains(&canonical) {
+        return Err(ParseError::IncludeCycle(name.to_string()));
+    }
+
+    let file = File::open(path)?;
+
+    #[allow(unused_mut)]
+    let mut reader = BufReader::new(&file);
+
+    #[cfg(feature = "gzip")]
+    let compressed = is_gzip(path, &mut reader)?;
+
+    context.chain.push(canonical);
+
+    #[cfg(feature = "gzip")]
+    let parsed = match compressed {
+        true => {
+            let decoder = BufReader::new(flate2::bufread::GzDecoder::new(reader));
+            parse_lines(decoder, options, context, progress)
+        }
+        false => parse_lines(reader, options, context, progress),
+    };
+    #[cfg(not(feature = "gzip"))]
+    let parsed = parse_lines(reader, options, context, progress);
+
+    context.chain.pop();
+
+    let mut response = parsed?;
+
+    for error in response.errors.iter_mut() {
+        error.file.get_or_insert_with(|| name.to_string());
+    }
+
+    for warning in response.warnings.iter_mut() {
+        warning.file.get_or_insert_with(|| name.to_string());
+    }
+
+    return Ok(response);
+}
+
+/// Проверяет файл, не сохраняя результат парсинга.
+///
+/// Работает так же, как [`parse`], но возвращает только ошибки и предупреждения
+/// (см. [`ValidationReport`]). Подходит для проверки файлов перевода, например, в CI.
+pub fn validate(path: &Path, options: &ParseOptions) -> Result<ValidationReport, ParseError> {
+    return parse(path, options).map(|response| ValidationReport::from(*response));
+}
+
+/// Парсит все файлы `.txt` в каталоге и объединяет результат в один объект-ответ.
+///
+/// * `dir: &`[`Path`] - каталог с файлами.
+/// * `recursive: `[`bool`] - обходить ли вложенные каталоги.
+/// * `options: &`[`ParseOptions`] - настройки парсинга, общие для всех файлов.
+///
+/// Файлы обрабатываются в алфавитном порядке. Поля с одинаковым набором тэгов
+/// из разных файлов объединяются так же, как и внутри одного файла (см. [`update_response`]),
+/// а у каждой ошибки и предупреждения указывается путь до файла относительно `dir`.
+/// В `separator` записывается разделитель первого файла. С опцией `gzip`
+/// обрабатываются также файлы `.txt.gz`. Повторы, сортировка и повторяющиеся оригиналы
+/// (см. [`ParseOptions::dedup_content`]) обрабатываются уже в объединенных полях,
+/// поэтому у предупреждений о повторяющихся оригиналах путь до файла не указывается.
+pub fn parse_dir(
+    dir: &Path,
+    recursive: bool,
+    options: &ParseOptions,
+) -> Result<Box<Response>, ParseError> {
+    let mut response = Response::new(options);
+
+    for path in collect_files(dir, recursive)? {
+        let parsed = parse_dir_file(dir, &path, options)?;
+        merge_dir_file(&mut response, *parsed, options);
+    }
+
+    process_content(&mut response, options);
+
+    return Ok(Box::new(response));
+}
+
+/// Работает так же, как [`parse_dir`], но парсит файлы параллельно.
+///
+/// Результаты объединяются в том же алфавитном порядке файлов, поэтому совпадают
+/// с результатом [`parse_dir`]. Доступно только с опцией `parallel`.
+#[cfg(feature = "parallel")]
+pub fn parse_dir_parallel(
+    dir: &Path,
+    recursive: bool,
+    options: &ParseOptions,
+) -> Result<Box<Response>, ParseError> {
+    use rayon::prelude::*;
+
+    let parsed: Vec<Box<Response>> = collect_files(dir, recursive)?
+        .par_iter()
+        .map(|path| parse_dir_file(dir, path, options))
+        .collect::<Result<_, _>>()?;
+
+    let mut response = Response::new(options);
+
+    for parsed in parsed {
+        merge_dir_file(&mut response, *parsed, options);
+    }
+
+    process_content(&mut response, options);
+
+    return Ok(Box::new(response));
+}
+
+/// Парсит файл из каталога `dir`, указывая у ошибок и предупреждений путь относительно `dir`.
+fn parse_dir_file(
+    dir: &Path,
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<Box<Response>, ParseError> {
+    let file_name = path.strip_prefix(dir).unwrap_or(path).display().to_string();
+
+    let mut context = IncludeContext {
+        merged: true,
+        ..Default::default()
+    };
+
+    return parse_file(path, &file_name, options, &mut context, None);
+}
+
+/// Добавляет результат парсинга одного файла к результату [`parse_dir`].
+fn merge_dir_file(response: &mut Response, mut parsed: Response, options: &ParseOptions) {
+    if response.separator.is_empty() {
+        response.separator = mem::take(&mut parsed.separator);
+    }
+
+    if response.header_comments.is_empty() {
+        response.header_comments = mem::take(&mut parsed.header_comments);
+    }
+
+    response.all_tags.append(&mut parsed.all_tags);
+    append_errors(response, &mut parsed, options);
+    response.warnings.append(&mut parsed.warnings);
+
+    for field in parsed.fields.drain(..) {
+        merge_field(response, field, options.keep_empty_fields);
+    }
+}
+
+/// Проверяет, сжат ли файл gzip: по расширению `.gz` или по сигнатуре в начале файла.
+#[cfg(feature = "gzip")]
+fn is_gzip<R: BufRead>(path: &Path, reader: &mut R) -> io::Result<bool> {
+    const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        return Ok(true);
+    }
+
+    return Ok(reader.fill_buf()?.starts_with(&MAGIC));
+}
+
+/// Собирает отсортированный список файлов `.txt` в каталоге (и во вложенных, если `recursive`).
+fn collect_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, ParseError> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            if recursive {
+                files.extend(collect_files(&path, recursive)?);
+            }
+        } else if is_text_file(&path) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    return Ok(files);
+}
+
+/// Проверяет, подходит ли файл для [`parse_dir`]: `.txt`, а с опцией `gzip` еще и `.txt.gz`.
+fn is_text_file(path: &Path) -> bool {
+    #[cfg(feature = "gzip")]
+    if path.to_string_lossy().ends_with(".txt.gz") {
+        return true;
+    }
+
+    return path.extension().is_some_and(|ext| ext == "txt");
+}
+
+/// Парсит текст, уже загруженный в память.
+///
+/// Работает так же, как [`parse`], но вместо пути до файла принимает его содержимое.
+pub fn parse_str(input: &str, options: &ParseOptions) -> Result<Box<Response>, ParseError> {
+    return parse_reader(input.as_bytes(), options);
+}
+
+/// Парсит содержимое произвольного источника, поддерживающего построчное чтение.
+///
+/// Источник читается ровно один раз, поэтому подходит и для потоков без перемещения
+/// (стандартный ввод, сетевое соединение). Директивы `@sep` и `@lang` учитываются,
+/// только если стоят в начале файла, до первой строки с текстом или тэгами
+/// (пустые строки и комментарии при этом пропускаются). Действует только первая
+/// директива `@sep`; о повторных и о стоящих после начала текста сообщается
+/// предупреждением [`WarningKind::MisplacedDirective`].
+///
+/// Пустой источник, как и источник только из комментариев и директив, не считается
+/// ошибкой: возвращается объект-ответ без полей.
+///
+/// Активные тэги образуют одно общее множество. `#tag` добавляет в него один тэг,
+/// а `##tag` убирает его; `@tags a, b` и `@@tags a, b` добавляют и убирают сразу
+/// несколько тэгов. Способ записи не важен: тэг, добавленный через `@tags`, можно
+/// убрать через `##`, и наоборот. Повторное добавление активного тэга ничего не меняет,
+/// поэтому одно удаление снимает его полностью. `##` без имени убирает последний
+/// из еще активных тэгов (для `@tags a, b` последним считается `b`). Каждый текст
+/// попадает в поле с тем множеством тэгов, которое было активно в момент его чтения,
+/// например:
+///
+/// ```text
+/// #a
+/// @tags b, c
+/// x -- 1      // тэги a, b, c
+/// ##a
+/// y -- 2      // тэги b, c
+/// @@tags b
+/// z -- 3      // тэг c
+/// ```
+///
+/// Длинный список `@tags` (или `@@tags`) можно продолжить на следующих строках,
+/// закончив строку запятой: список продолжается до первой строки без запятой в конце.
+/// Строка с директивой тэга или с разделителем обрывает список.
+///
+/// Оригинал и перевод можно заключить в двойные кавычки: текст внутри берется как есть,
+/// разделители, `#` и запрещенные символы в нем не учитываются, а `""` заменяется на `"`.
+/// Например, из строки `"a = b" = "c # d"` (с разделителем `=`) получается оригинал `a = b`
+/// и перевод `c # d`. Незакрытая кавычка считается ошибкой.
+///
+/// Все, что стоит после маркера комментария (по умолчанию `//`, см.
+/// [`ParseOptions::inline_comment`]), отбрасывается, например `Hello -- Hallo // приветствие`.
+///
+/// Строка `@comment <заметка>` добавляет заметку к полю, которое собирается в данный
+/// момент (с текущим набором тэгов). В отличие от комментариев `//`, заметки не
+/// отбрасываются, а сохраняются в поле вместе с его текстами.
+///
+/// Строки между `@raw` и `@@raw` не разбиваются по разделителю: вся строка
+/// целиком становится оригиналом без перевода, даже если в ней есть разделитель.
+/// Директивы тэгов внутри блока действуют как обычно.
+///
+/// Директива `@include <путь>` в любом месте файла подставляет поля другого файла
+/// так, как если бы его текст стоял на месте директивы: к тэгам включенных полей
+/// добавляются тэги, активные на момент включения. Директива обрабатывается только
+/// при [`ParseOptions::allow_include`]. Относительный путь считается
+/// от каталога включающего файла, а для [`parse_str`] и [`parse_reader`] - от
+/// текущего рабочего каталога. Абсолютные пути и пути с `..` разрешены только при
+/// [`ParseOptions::allow_include_outside`]. Если файл прямо или косвенно включает
+/// сам себя, возвращается [`ParseError::IncludeCycle`].
+///
+/// Директива `@define ИМЯ=значение` задает переменную: в следующих строках файла
+/// `${ИМЯ}` в оригинале и переводе заменяется на значение до обрезки пробелов.
+/// Значения подставляются как есть, без раскрытия переменных внутри них.
+/// Неизвестная переменная остается в тексте без изменений (см.
+/// [`ParseOptions::warn_undefined_variables`]), а `\${` записывает `${` без подстановки.
+/// Имя состоит из букв, цифр и `_`; значение не может содержать запрещенные символы.
+pub fn parse_reader<R: BufRead>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<Box<Response>, ParseError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse").entered();
+
+    return parse_lines(reader, options, &mut IncludeContext::default(), None);
+}
+
+/// Общая часть [`parse_reader`] и [`parse_file`].
+fn parse_lines<R: BufRead>(
+    reader: R,
+    options: &ParseOptions,
+    context: &mut IncludeContext,
+    mut progress: Option<Progress>,
+) -> Result<Box<Response>, ParseError> {
+    let mut response = Response::new(options);
+
+    let mut content: Vec<Text> = Default::default();
+    let mut tags: HashSet<Tag> = Default::default();
+    // Порядок, в котором открывались активные тэги, и строки, где они были открыты,
+    // нужны для `##` без имени и для поиска незакрытых тэгов.
+    let mut tag_order: Vec<(Tag, u32)> = Default::default();
+    // Заметки `@comment` для поля, которое собирается в данный момент.
+    let mut comments: Vec<String> = Default::default();
+    // Языки, заданные директивой `@lang` посреди файла, для следующих полей.
+    let mut languages: Option<Languages> = None;
+    // Внутри блока `@raw ... @@raw` строки не разбиваются по разделителю.
+    let mut raw_block = false;
+    // Переменные из директив `@define`.
+    let mut variables: HashMap<String, String> = Default::default();
+
+    let mut string: String;
+
+    let mut lines = LineReader::new(reader, options)?;
+    let mut sampled: Vec<Line> = Default::default();
+    let mut detected: Option<String> = None;
+
+    if let Some(sep) = &options.separator {
+        if sep.is_empty() {
+            return Err(ParseError::InvalidSeparator(sep.to_string()));
+        }
+    }
+
+    // Директива `@sep auto` включает определение разделителя так же, как настройка,
+    // и, как любая директива, имеет приоритет над заданным в настройках разделителем.
+    let auto_directive = requests_auto_separator(&mut lines, &mut sampled);
+
+    if auto_directive || (options.separator.is_none() && options.auto_detect_separator) {
+        detected = detect_separator(&mut lines, &mut sampled);
+    }
+
+    let auto_failed = auto_directive && detected.is_none();
+    let preferred = match auto_directive {
+        true => None,
+        false => options.separator.clone(),
+    };
+
+    let mut sep = preferred
+        .or(detected)
+        .unwrap_or_else(|| DEFAULT_SEPARATOR.to_string());
+    let mut sep_declared = false;
+    let mut preamble = true;
+    let mut bytes_read: usize = 0;
+
+    let inline_comment = options
+        .inline_comment
+        .as_deref()
+        .filter(|marker| !marker.is_empty());
+
+    let syntax = TagSyntax::new(options);
+    check_separator_conflicts(&sep, &syntax, inline_comment)?;
+
+    let error_reg = match options.forbidden_chars.is_empty() {
+        true => None,
+        false => {
+            Some(Regex::new(&format!("[{}]+", regex::escape(&options.forbidden_chars))).unwrap())
+        }
+    };
+
+    // Просмотр следующих строк нужен для списков `@tags`, продолжающихся на нескольких строках.
+    let mut numbered = sampled.into_iter().chain(lines).enumerate().peekable();
+
+    while let Some((index, line)) = numbered.next() {
+        let num_line = index as u32 + 1;
+
+        bytes_read += line.bytes;
+        count_line(options, &mut progress, index, bytes_read)?;
+
+        let raw = match line.text {
+            Ok(x) => x,
+            Err(err) if options.fail_on_read_error || err.kind() != io::ErrorKind::InvalidData => {
+                return Err(ParseError::Io(err))
+            }
+            Err(err) => {
+                response.warnings.push(Warning {
+                    location: Location::new(num_line),
+                    kind: WarningKind::ReadError,
+                    message: format!("строка не прочитана и пропущена: {err}"),
+                    file: None,
+                });
+
+                "".to_string()
+            }
+        };
+
+        if let Some(len) = line.too_long {
+            response.warnings.push(Warning {
+                location: Location::new(num_line),
+                kind: WarningKind::LineTooLong,
+                message: format!("строка длиной {len} байт пропущена"),
+                file: None,
+            });
+
+            continue;
+        }
+
+        let source = options.keep_raw.then(|| raw.clone());
+
+        // Маркер `//` здесь не зависит от `inline_comment`, как и в `skip_line_else`.
+        if preamble && options.keep_header_comments {
+            if let Some(comment) = raw.trim_start().strip_prefix("//") {
+                response.header_comments.push(comment.to_string());
+                continue;
+            }
+        }
+
+        // Директива `@sep` распознается до удаления комментария: если после удаления
+        // от значения ничего не остается, разделителем считается само значение
+        // (например, `@sep //`), чтобы ошибка называла именно его.
+        let unstripped_sep = separator_directive(raw.trim()).map(|value| value.to_string());
+
+        let mut raw = match inline_comment {
+            Some(marker) => strip_inline_comment(raw, marker, &sep),
+            None => raw,
+        };
+
+        // Строка, оканчивающаяся на `\`, продолжается следующей (см. `line_continuation`).
+        // Непрочитанная или пропущенная из-за длины строка ее не продолжает, а обрабатывается
+        // отдельно.
+        while options.line_continuation && raw.trim_end().ends_with('\\') {
+            let Some((index, line)) =
+                numbered.next_if(|(_, line)| line.text.is_ok() && line.too_long.is_none())
+            else {
+                break;
+            };
+
+            bytes_read += line.bytes;
+            count_line(options, &mut progress, index, bytes_read)?;
+
+            let line = line.text.unwrap_or_default();
+
+            let line = match inline_comment {
+                Some(marker) => strip_inline_comment(line, marker, &sep),
+                None => line,
+            };
+
+            raw.truncate(raw.trim_end().len() - 1);
+            raw.push_str(line.trim_start());
+        }
+
+        string = raw.trim().to_string();
+
+        if preamble {
+            if let Some(value) = separator_directive(&string) {
+                let value = match (value.trim().is_empty(), &unstripped_sep) {
+                    (true, Some(unstripped)) => unstripped.as_str(),
+                    _ => value,
+                };
+
+                note_directive(
+                    &mut response,
+                    options,
+                    DirectiveKind::Separator,
+                    num_line,
+                    &string,
+                );
+
+                if sep_declared {
+                    response.warnings.push(ignored_separator_warning(num_line));
+                } else if is_auto_separator(value) {
+                    // Разделитель уже определен до начала разбора строк.
+                    if !auto_directive {
+                        response.warnings.push(Warning {
+                            location: Location::new(num_line),
+                            kind: WarningKind::AmbiguousSeparator,
+                            message: format!(
+                                "директива `@sep auto` учитывается только в первых {SEPARATOR_SCAN_LINES} строках и без комментария в конце, используется `{sep}`"
+                            ),
+                            file: None,
+                        });
+                    } else if auto_failed {
+                        response.warnings.push(Warning {
+                            location: Location::new(num_line),
+                            kind: WarningKind::AmbiguousSeparator,
+                            message: format!(
+                                "не удалось определить разделитель, используется `{sep}`"
+                            ),
+                            file: None,
+                        });
+                    }
+
+                    sep_declared = true;
+                } else {
+                    sep = validate_separator(value)?;
+                    check_separator_conflicts(&sep, &syntax, inline_comment)?;
+                    sep_declared = true;
+                }
+
+                continue;
+            }
+
+            // Кодировка выбирается при чтении, поэтому здесь директива только пропускается.
+            if charset_directive(&string).is_some() {
+                note_directive(
+                    &mut response,
+                    options,
+                    DirectiveKind::Charset,
+                    num_line,
+                    &string,
+                );
+
+                if index != 0 {
+                    response.warnings.push(Warning {
+                        location: Location::new(num_line),
+                        kind: WarningKind::MisplacedDirective,
+                        message: "директива `@charset` учитывается только в первой строке файла"
+                            .to_string(),
+                        file: None,
+                    });
+                }
+
+                continue;
+            }
+
+            if let Some(value) = language_directive(&string) {
+                note_directive(
+                    &mut response,
+                    options,
+                    DirectiveKind::Language,
+                    num_line,
+                    &string,
+                );
+
+                match value {
+                    Some(_) if options.force_languages.is_some() => {}
+                    Some(languages) => response.languages = languages,
+                    None => response.warnings.push(invalid_language_warning(num_line)),
+                }
+
+                continue;
+            }
+
+            preamble = string.is_empty() || string.starts_with("//");
+        } else if separator_directive(&string).is_some() {
+            note_directive(
+                &mut response,
+                options,
+                DirectiveKind::Separator,
+                num_line,
+                &string,
+            );
+            response.warnings.push(ignored_separator_warning(num_line));
+            continue;
+        }
+
+        // После начала текста `@lang` меняет языки только для следующих полей.
+        if !preamble {
+            if let Some(value) = language_directive(&string) {
+                note_directive(
+                    &mut response,
+                    options,
+                    DirectiveKind::Language,
+                    num_line,
+                    &string,
+                );
+
+                let Some(value) = value else {
+                    response.warnings.push(invalid_language_warning(num_line));
+                    continue;
+                };
+
+                if options.force_languages.is_some() {
+                    continue;
+                }
+
+                if options.validate_languages {
+                    validate_language(&value.original)?;
+                    validate_language(&value.translate)?;
+                }
+
+                update_response(
+                    &mut response,
+                    &mut content,
+                    &tags,
+                    &mut comments,
+                    &languages,
+                    options.keep_empty_fields,
+                );
+
+                // Языки, совпадающие с языками файла, не хранятся в поле.
+                languages = (value != response.languages).then_some(value);
+                continue;
+            }
+        }
+
+        if skip_line_else(&string) {
+            continue;
+        }
+
+        if let Some(predicate) = &options.skip_line_predicate {
+            if predicate(&string) {
+                continue;
+            }
+        }
+
+        // Список `@tags`, оканчивающийся запятой, продолжается на следующих строках.
+        // Пустые строки и комментарии внутри списка пропускаются, а строка с директивой
+        // тэга или с разделителем обрывает список и дальше обрабатывается как обычно.
+        let is_bulk =
+            string.starts_with(&syntax.bulk_open) || string.starts_with(&syntax.bulk_close);
+
+        if is_bulk && string.ends_with(',') {
+            while string.ends_with(',') {
+                // Непрочитанная или слишком длинная строка не продолжает список, а обрабатывается
+                // отдельно.
+                let next = numbered.next_if(|(_, line)| {
+                    let (Ok(line), None) = (&line.text, line.too_long) else {
+                        return false;
+                    };
+
+                    let text = tag_list_line(line, inline_comment, &sep);
+                    !syntax.tags_reg.is_match(&text) && !text.contains(sep.as_str())
+                });
+
+                let Some((index, line)) = next else {
+                    break;
+                };
+
+                bytes_read += line.bytes;
+                count_line(options, &mut progress, index, bytes_read)?;
+
+                let line = line.text.unwrap_or_default();
+
+                let text = tag_list_line(&line, inline_comment, &sep);
+
+                if !skip_line_else(&text) {
+                    string.push(' ');
+                    string.push_str(&text);
+                }
+            }
+
+            if string.ends_with(',') {
+                response.warnings.push(Warning {
+                    location: Location::new(num_line),
+                    kind: WarningKind::InvalidDirective,
+                    message: "список тэгов оканчивается запятой, но не продолжен \
+                              на следующей строке"
+                        .to_string(),
+                    file: None,
+                });
+            }
+        }
+
+        if string == RAW_OPEN || string == RAW_CLOSE {
+            note_directive(
+                &mut response,
+                options,
+                DirectiveKind::Raw,
+                num_line,
+                &string,
+            );
+            raw_block = string == RAW_OPEN;
+            continue;
+        }
+
+        // Заметка, как и путь в `@include`, может содержать запрещенные символы,
+        // поэтому директива обрабатывается до поиска ошибок.
+        if let Some(comment) = comment_directive(&string) {
+            note_directive(
+                &mut response,
+                options,
+                DirectiveKind::Comment,
+                num_line,
+                &string,
+            );
+
+            let comment = match inline_comment {
+                Some(marker) => comment.replace(&format!("\\{marker}"), marker),
+                None => comment.to_string(),
+            };
+
+            comments.push(comment);
+            continue;
+        }
+
+        if let Some(value) = define_directive(&string) {
+            note_directive(
+                &mut response,
+                options,
+                DirectiveKind::Define,
+                num_line,
+                &string,
+            );
+
+            let value = value
+                .filter(|(_, value)| !error_reg.as_ref().is_some_and(|reg| reg.is_match(value)));
+
+            let Some((name, value)) = value else {
+                response.warnings.push(Warning {
+                    location: Location::new(num_line),
+                    kind: WarningKind::InvalidDirective,
+                    message: "директива `@define` должна иметь вид `@define ИМЯ=значение` \
+                              без запрещенных символов в значении"
+                        .to_string(),
+                    file: None,
+                });
+                continue;
+            };
+
+            variables.insert(name.to_string(), value.to_string());
+            continue;
+        }
+
+        // Путь может содержать запрещенные в тексте символы (`/`, `:`),
+        // поэтому директива обрабатывается до поиска ошибок.
+        if let Some(include) = include_directive(&string) {
+            note_directive(
+                &mut response,
+                options,
+                DirectiveKind::Include,
+                num_line,
+                &string,
+            );
+
+            if !options.allow_include {
+                response.warnings.push(Warning {
+                    location: Location::new(num_line),
+                    kind: WarningKind::DisabledInclude,
+                    message: format!("включение файлов выключено, `{include}` пропущен"),
+                    file: None,
+                });
+
+                continue;
+            }
+
+            if !options.allow_include_outside && !is_nested_path(Path::new(include)) {
+                return Err(ParseError::ForbiddenInclude(include.to_string()));
+            }
+
+            let path = context.base_dir().join(include);
+            let merged = mem::replace(&mut context.merged, true);
+            let included = parse_file(&path, &path.display().to_string(), options, context, None);
+
+            context.merged = merged;
+
+            let mut included = included?;
+
+            update_response(
+                &mut response,
+                &mut content,
+                &tags,
+                &mut comments,
+                &languages,
+                options.keep_empty_fields,
+            );
+
+            for mut field in included.fields.drain(..) {
+                field.tags.extend(tags.iter().cloned());
+
+                if field.languages.is_none() {
+                    field.languages.clone_from(&languages);
+                }
+
+                merge_field(&mut response, field, options.keep_empty_fields);
+            }
+
+            response.all_tags.append(&mut included.all_tags);
+            append_errors(&mut response, &mut included, options);
+            response.warnings.append(&mut included.warnings);
+
+            continue;
+        }
+
+        // Известные директивы уже обработаны выше, кроме директив тэгов
+        // и `@charset` не в начале файла, которая остается текстом.
+        let unknown_directive = string.starts_with('@')
+            && !syntax.tags_reg.is_match(&string)
+            && charset_directive(&string).is_none();
+
+        if options.strict_directives && unknown_directive {
+            let directive = string.split_whitespace().next().unwrap_or("@").to_string();
+            let known = DIRECTIVES.contains(&directive.as_str())
+                || [&syntax.bulk_open, &syntax.bulk_close]
+                    .iter()
+                    .any(|name| name.trim() == directive);
+
+            return Err(match known {
+                true => ParseError::InvalidDirective {
+                    line: num_line,
+                    directive,
+                },
+                false => ParseError::UnknownDirective {
+                    line: num_line,
+                    directive,
+                },
+            });
+        }
+
+        // Строка, начинающаяся с `\#` (с обратной косой черты перед префиксом тэга),
+        // считается текстом, а не тэгом; обратная косая черта при этом отбрасывается.
+        let escaped_tag = string
+            .strip_prefix('\\')
+            .is_some_and(|rest| rest.starts_with(&syntax.open));
+
+        // Разделитель и разделитель альтернатив могут состоять из запрещенных символов
+        // (например, `->` или `|`), поэтому перед проверкой они заменяются пробелами той же длины.
+        // Экранированный разделитель заменяется вместе с обратной косой чертой.
+        let escaped_sep = format!("\\{sep}");
+        let mut checked = string
+            .replace(&escaped_sep, &" ".repeat(escaped_sep.len()))
+            .replace(sep.as_str(), &" ".repeat(sep.len()));
+
+        // Обратная косая черта перед `#` отбрасывается, поэтому тоже не проверяется.
+        if escaped_tag {
+            checked.replace_range(..1, " ");
+        }
+
+        if let Some(alt_sep) = &options.alternatives_separator {
+            if !alt_sep.is_empty() {
+                checked = checked.replace(alt_sep.as_str(), &" ".repeat(alt_sep.len()));
+            }
+        }
+
+        // Экранированный маркер комментария остается в тексте, поэтому тоже не проверяется.
+        if let Some(marker) = inline_comment {
+            let escaped_marker = format!("\\{marker}");
+            checked = checked.replace(&escaped_marker, &" ".repeat(escaped_marker.len()));
+        }
+
+        // Обратная косая черта перед `${` отбрасывается при подстановке переменных,
+        // поэтому тоже не проверяется.
+        checked = checked.replace(ESCAPED_VARIABLE, &" ".repeat(ESCAPED_VARIABLE.len()));
+
+        // Текст в кавычках не проверяется. Если кавычка не закрыта,
+        // она остается в строке и сама отмечается как ошибка.
+        for range in quoted_ranges(&string, &sep).unwrap_or_default() {
+            checked.replace_range(range.clone(), &" ".repeat(range.len()));
+        }
+
+        if let Some(error_reg) = error_reg.as_ref().filter(|reg| reg.is_match(&checked)) {
+            if options.fail_fast {
+                return Err(ParseError::InvalidContent {
+                    line: num_line,
+                    string,
+                });
+            }
+
+            let mut error = ErrorLine {
+                location: Location::new(num_line),
+                columns: Default::default(),
+                spans: Default::default(),
+                string: string.to_string(),
+                file: None,
+            };
+
+            // Замены выше сохраняют длину в байтах, но не в символах, поэтому номера
+            // символов считаются по исходной строке.
+            let char_index = |byte: usize| {
+                return string
+                    .char_indices()
+                    .take_while(|&(index, _)| index < byte)
+                    .count();
+            };
+
+            for column in error_reg.find_iter(&checked) {
+                error.columns.push(column.start());
+                error.spans.push(Span {
+                    start_char: char_index(column.start()),
+                    end_char: char_index(column.end()),
+                });
+            }
+
+            // Номер символа считается от начала строки файла, а не от строки без пробелов.
+            if let Some(first) = error.spans.first() {
+                let indent = raw[..raw.len() - raw.trim_start().len()].chars().count();
+                error.location = error
+                    .location
+                    .with_column((indent + first.start_char) as u32 + 1);
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::warn!(line = num_line, string = %error.string, "запрещенные символы в строке");
+
+            push_error(&mut response, options, error);
+
+            if !options.errors_as_warnings {
+                continue;
+            }
+        }
+
+        if escaped_tag {
+            string.remove(0);
+        }
+
+        if !escaped_tag && syntax.tags_reg.is_match(string.as_str()) {
+            let parsed_tags = canonical_tags(syntax.parse_tags(&string), options);
+            note_directive(
+                &mut response,
+                options,
+                DirectiveKind::Tags,
+                num_line,
+                &string,
+            );
+
+            update_response(
+                &mut response,
+                &mut content,
+                &tags,
+                &mut comments,
+                &languages,
+                options.keep_empty_fields,
+            );
+
+            // Строка считается закрывающей, если совпала с `remove_tags_reg`, даже когда
+            // начинается с открывающей директивы (например, `@tags a, @@tags b`).
+            // Такие строки неоднозначны, поэтому о них сообщается отдельно.
+            let is_close_prefix =
+                string.starts_with(&syntax.close) || string.starts_with(&syntax.bulk_close);
+
+            if syntax.remove_tags_reg.is_match(&string) != is_close_prefix {
+                if options.fail_on_ambiguous_directive {
+                    return Err(ParseError::AmbiguousDirective { line: num_line });
+                }
+
+                response.warnings.push(Warning {
+                    location: Location::new(num_line),
+                    kind: WarningKind::AmbiguousDirective,
+                    message: "директива одновременно похожа на открывающую и закрывающую, \
+                              строка обработана как закрывающая"
+                        .to_string(),
+                    file: None,
+                });
+            }
+
+            if string == syntax.close {
+                pop_tag(&mut tags, &mut tag_order);
+            } else if syntax.remove_tags_reg.is_match(&string) {
+                substract_tags(&mut tags, &mut tag_order, &parsed_tags);
+            } else {
+                let added = parsed_tags.iter().filter(|x| !tags.contains(*x)).count();
+
+                if options
+                    .max_active_tags
+                    .is_some_and(|max| tags.len() + added > max)
+                {
+                    if options.fail_on_too_many_tags {
+                        return Err(ParseError::TooManyTags { line: num_line });
+                    }
+
+                    response.warnings.push(Warning {
+                        location: Location::new(num_line),
+                        kind: WarningKind::TooManyTags,
+                        message: format!(
+                            "активных тэгов стало бы {}, директива пропущена",
+                            tags.len() + added
+                        ),
+                        file: None,
+                    });
+
+                    continue;
+                }
+
+                response.all_tags.extend(parsed_tags.iter().cloned());
+                extend_tags(&mut tags, &mut tag_order, &parsed_tags, num_line);
+            }
+        } else {
+            // Без обрезки текст берется из исходной строки (без `\` перед `#`),
+            // поэтому пробелы по краям и вокруг разделителя сохраняются.
+            let untrimmed: String;
+            let line = match options.trim {
+                true => string.as_str(),
+                false => {
+                    untrimmed = match escaped_tag {
+                        true => {
+                            let mut x = raw.clone();
+                            x.remove(raw.len() - raw.trim_start().len());
+                            x
+                        }
+                        false => raw.clone(),
+                    };
+                    untrimmed.as_str()
+                }
+            };
+
+            let quoted = quoted_ranges(line, &sep).unwrap_or_default();
+            let separators: Vec<usize> = match raw_block {
+                true => Default::default(),
+                false => unescaped_indices(line, &sep)
+                    .into_iter()
+                    .filter(|index| !quoted.iter().any(|range| range.contains(index)))
+                    .collect(),
+            };
+
+            if options.split_from == SeparatorMode::Last && separators.len() > 1 {
+                response.warnings.push(Warning {
+                    location: Location::new(num_line),
+                    kind: WarningKind::AmbiguousSeparator,
+                    message: format!(
+                        "в строке {} разделителей `{sep}`, перевод отделен по последнему",
+                        separators.len()
+                    ),
+                    file: None,
+                });
+            }
+
+            let split = match options.split_from {
+                SeparatorMode::First => separators.first(),
+                SeparatorMode::Last => separators.last(),
+            }
+            .map(|&index| (&line[..index], &line[index + sep.len()..]));
+
+            let (original, translate) = match split {
+                Some(x) => x,
+                None => (line, ""),
+            };
+
+            let sep_offset = match split {
+                Some(_) if options.track_sep_offset => {
+                    let indent = match options.trim {
+                        true => raw.chars().count() - raw.trim_start().chars().count(),
+                        false => 0,
+                    };
+                    Some(indent + usize::from(escaped_tag) + original.chars().count())
+                }
+                _ => None,
+            };
+
+            // Текст в кавычках берется как есть: без снятия экранирования,
+            // нормализации, обрезки и разбиения на альтернативы.
+            let original_quoted = unquote(original);
+            let translate_quoted = unquote(translate);
+
+            // Пока текст не меняется, он заимствуется из строки, и память
+            // выделяется один раз, при создании `Text`.
+            let escaped_sep = format!("\\{sep}");
+            let mut original = replace_cow(Cow::Borrowed(original), &escaped_sep, &sep);
+            let mut translate = replace_cow(Cow::Borrowed(translate), &escaped_sep, &sep);
+
+            if let Some(marker) = inline_comment {
+                let escaped_marker = format!("\\{marker}");
+
+                original = replace_cow(original, &escaped_marker, marker);
+                translate = replace_cow(translate, &escaped_marker, marker);
+            }
+
+            let mut undefined: Vec<String> = Default::default();
+
+            if original_quoted.is_none() {
+                original = expand_variables(original, &variables, &mut undefined);
+            }
+
+            if translate_quoted.is_none() {
+                translate = expand_variables(translate, &variables, &mut undefined);
+            }
+
+            if options.warn_undefined_variables {
+                for name in undefined {
+                    response.warnings.push(Warning {
+                        location: Location::new(num_line),
+                        kind: WarningKind::UndefinedVariable,
+                        message: format!("переменная `{name}` не задана директивой `@define`"),
+                        file: None,
+                    });
+                }
+            }
+
+            // Нормализация выполняется после разбиения строки, чтобы длинное тире
+            // не превратилось в разделитель по умолчанию `--`.
+            let (original, translate) = if options.normalize_typography {
+                (
+                    Cow::Owned(normalize_typography(&original)),
+                    Cow::Owned(normalize_typography(&translate)),
+                )
+            } else {
+                (original, translate)
+            };
+
+            let translates = match &options.alternatives_separator {
+                Some(_) if translate_quoted.is_some() => Default::default(),
+                Some(alt_sep) if !alt_sep.is_empty() => split_alternatives(&translate, alt_sep),
+                _ => Default::default(),
+            };
+
+            let original = match original_quoted {
+                Some(x) => x,
+                None if options.normalize_original_whitespace_only => {
+                    collapse_whitespace(&original)
+                }
+                None if options.trim => String::from(original.trim()),
+                None => original.into_owned(),
+            };
+
+            let text = Text {
+                original,
+                translate: match (translate_quoted, translates.first()) {
+                    (Some(x), _) => x,
+                    (None, Some(first)) => first.to_string(),
+                    (None, None) if options.trim => String::from(translate.trim()),
+                    (None, None) => translate.into_owned(),
+                },
+                translates,
+                sep_offset,
+                line: num_line,
+                raw: source,
+            };
+
+            #[cfg(feature = "unicode-normalization")]
+            let text = match options.normalize {
+                true => normalize_nfc(text),
+                false => text,
+            };
+
+            let langs = languages.as_ref().unwrap_or(&response.languages);
+            let columns = [
+                (&langs.original, &text.original),
+                (&langs.translate, &text.translate),
+            ];
+
+            for (lang, value) in columns {
+                let Some(allowed) = options.allowed_chars_per_lang.get(lang) else {
+                    continue;
+                };
+
+                let stray = find_stray_chars(value, allowed);
+
+                if !stray.is_empty() {
+                    response.warnings.push(Warning {
+                        location: Location::new(num_line),
+                        kind: WarningKind::DisallowedCharacters,
+                        message: format!("недопустимые для языка `{lang}` символы: {stray}"),
+                        file: None,
+                    });
+                }
+            }
+
+            content.push(text);
+        }
+    }
+
+    update_response(
+        &mut response,
+        &mut content,
+        &tags,
+        &mut comments,
+        &languages,
+        options.keep_empty_fields,
+    );
+
+    response.separator = sep;
+
+    if options.warn_unclosed_tags {
+        for (tag, line) in tag_order {
+            response.warnings.push(Warning {
+                location: Location::new(line),
+                kind: WarningKind::UnclosedTag,
+                message: format!("тэг `{tag}` не закрыт до конца файла"),
+                file: None,
+            });
+        }
+    }
+
+    if options.validate_languages {
+        validate_language(&response.languages.original)?;
+        validate_language(&response.languages.translate)?;
+    }
+
+    if !context.merged {
+        process_content(&mut response, options);
+    }
+
+    return Ok(Box::new(response));
+}
+
+/// Убирает повторы текстов, сортирует их и ищет повторяющиеся оригиналы, если это
+/// включено в `options`. Выполняется один раз для итогового результата, чтобы учитывать
+/// тексты всех объединенных файлов.
+fn process_content(response: &mut Response, options: &ParseOptions) {
+    if options.dedup_content {
+        for field in response.fields.iter_mut() {
+            dedup_texts(&mut field.content);
+        }
+    }
+
+    if options.sort_content {
+        for field in response.fields.iter_mut() {
+            field
+                .content
+                .sort_by_cached_key(|text| text.original.to_lowercase());
+        }
+    }
+
+    if options.detect_duplicates {
+        find_duplicate_originals(response);
+    }
+}
+
+/// Убирает повторы текстов с тем же оригиналом и переводами, оставляя первое вхождение.
+fn dedup_texts(content: &mut Vec<Text>) {
+    let mut seen: HashSet<(String, String, Vec<String>)> = Default::default();
+
+    content.retain(|text| {
+        return seen.insert((
+            text.original.clone(),
+            text.translate.clone(),
+            text.translates.clone(),
+        ));
+    });
+}
+
+/// Добавляет ошибку в результат, если в нем еще меньше [`ParseOptions::max_errors`] ошибок.
+/// Иначе ошибка отбрасывается, а в результате отмечается, что ошибки неполные.
+fn push_error(response: &mut Response, options: &ParseOptions, error: ErrorLine) {
+    if options
+        .max_errors
+        .is_some_and(|max| response.errors.len() >= max)
+    {
+        response.errors_truncated = true;
+        return;
+    }
+
+    response.errors.push(error);
+}
+
+/// Переносит ошибки из результата парсинга другого файла с учетом [`ParseOptions::max_errors`].
+fn append_errors(response: &mut Response, other: &mut Response, options: &ParseOptions) {
+    for error in other.errors.drain(..) {
+        push_error(response, options, error);
+    }
+
+    response.errors_truncated |= other.errors_truncated;
+}
+
+/// Возвращает `true` для неустановленного флага, чтобы он не попадал в JSON.
+fn is_false(value: &bool) -> bool {
+    return !value;
+}
+
+/// Отмечает распознанную директиву: записывает ее в [`Response`]
+/// при [`ParseOptions::collect_directives`] и, с возможностью `tracing`, создает событие.
+fn note_directive(
+    response: &mut Response,
+    options: &ParseOptions,
+    kind: DirectiveKind,
+    line: u32,
+    raw: &str,
+) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(line, directive = ?kind, raw, "директива");
+
+    if options.collect_directives {
+        response.directives.push(DirectiveInfo {
+            kind,
+            location: Location::new(line),
+            raw: raw.to_string(),
+        });
+    }
+}
+
+/// Проверяет ограничения [`ParseOptions::max_lines`] и [`ParseOptions::max_bytes`]
+/// после чтения строки с номером `index` (с нуля) и сообщает о ходе парсинга.
+fn count_line(
+    options: &ParseOptions,
+    progress: &mut Option<Progress>,
+    index: usize,
+    bytes_read: usize,
+) -> Result<(), ParseError> {
+    let over_lines = options.max_lines.is_some_and(|max| index >= max);
+    let over_bytes = options.max_bytes.is_some_and(|max| bytes_read > max);
+
+    if over_lines || over_bytes {
+        return Err(ParseError::LimitExceeded {
+            line: index as u32 + 1,
+        });
+    }
+
+    if let Some(progress) = progress.as_mut() {
+        if (index + 1).is_multiple_of(progress.interval) {
+            (progress.callback)(index + 1);
+        }
+    }
+
+    return Ok(());
+}
+
+/// Строка-продолжение списка `@tags` без комментария в конце и пробелов по краям.
+fn tag_list_line(line: &str, inline_comment: Option<&str>, sep: &str) -> String {
+    let raw = match inline_comment {
+        Some(marker) => strip_inline_comment(line.to_string(), marker, sep),
+        None => line.to_string(),
+    };
+
+    return raw.trim().to_string();
+}
+
+/// Отбрасывает комментарий в конце строки (см. [`ParseOptions::inline_comment`]):
+/// все, начиная с первого маркера, перед которым нет обратной косой черты
+/// и который не стоит внутри кавычек.
+fn strip_inline_comment(mut line: String, marker: &str, sep: &str) -> String {
+    let quoted = quoted_ranges(&line, sep).unwrap_or_default();
+
+    let start = unescaped_indices(&line, marker)
+        .into_iter()
+        .find(|index| !quoted.iter().any(|range| range.contains(index)));
+
+    if let Some(start) = start {
+        line.truncate(start);
+    }
+
+    return line;
+}
+
+/// Проверяет, что код языка имеет вид тэга BCP 47 `язык[-письменность][-регион][-вариант]...`
+/// и язык входит в [`ISO_639_1`] (пустой код допускается).
+///
+/// Проверяется только вид подтэгов: письменность - 4 буквы, регион - 2 буквы или 3 цифры,
+/// вариант - от 5 до 8 букв и цифр или 4, если первый символ - цифра.
+fn validate_language(code: &str) -> Result<(), ParseError> {
+    if code.is_empty() {
+        return Ok(());
+    }
+
+    let mut subtags = code.split('-').peekable();
+    let language = subtags.next().unwrap_or_default();
+
+    let is_alpha =
+        |x: &str, len: usize| x.len() == len && x.chars().all(|x| x.is_ascii_alphabetic());
+
+    subtags.next_if(|x| is_alpha(x, 4));
+    subtags.next_if(|x| is_alpha(x, 2) || x.len() == 3 && x.chars().all(|x| x.is_ascii_digit()));
+
+    let variants = subtags.all(|x| {
+        let alphanumeric = x.chars().all(|x| x.is_ascii_alphanumeric());
+
+        return match x.len() {
+            5..=8 => alphanumeric,
+            4 => alphanumeric && x.starts_with(|x: char| x.is_ascii_digit()),
+            _ => false,
+        };
+    });
+
+    if variants && ISO_639_1.contains(&language.to_lowercase().as_str()) {
+        return Ok(());
+    }
+
+    return Err(ParseError::UnknownLanguage(code.to_string()));
+}
+
+/// Определяет, пустая ли строка или начинается ли она с комментария
+/// (строка начинается с "//"). Директивы `@sep` обрабатываются до этой проверки.
+fn skip_line_else(string: &str) -> bool {
+    return string.is_empty() || string.starts_with("//");
+}
+
+/// Возвращает байтовые позиции всех разделителей в строке, перед которыми нет обратной косой черты.
+///
+/// Экранированный разделитель (`\=` при разделителе `=`) считается частью текста,
+/// снятие экранирования выполняется уже после разбиения строки.
+fn unescaped_indices(string: &str, sep: &str) -> Vec<usize> {
+    if sep.is_empty() {
+        return vec![0];
+    }
+
+    return string
+        .match_indices(sep)
+        .map(|(index, _)| index)
+        .filter(|&index| !string[..index].ends_with('\\'))
+        .collect();
+}
+
+/// Возвращает байтовые диапазоны частей строки, заключенных в двойные кавычки
+/// (вместе с самими кавычками).
+///
+/// Кавычка открывает такую часть, только если стоит в начале оригинала или перевода,
+/// то есть в начале строки или сразу после разделителя (пробелы перед ней допускаются).
+/// Разделители внутри кавычек не учитываются, а `""` означает саму кавычку.
+/// Если кавычка не закрыта, возвращается [`Err`] с ее позицией.
+fn quoted_ranges(string: &str, sep: &str) -> Result<Vec<Range<usize>>, usize> {
+    let separators = unescaped_indices(string, sep);
+
+    let mut ranges: Vec<Range<usize>> = Default::default();
+    let mut pos = 0;
+
+    while !sep.is_empty() {
+        let start = pos + string[pos..].len() - string[pos..].trim_start().len();
+
+        if string[start..].starts_with('"') {
+            let end = closing_quote(string, start + 1).ok_or(start)?;
+
+            ranges.push(start..end + 1);
+            pos = end + 1;
+        }
+
+        match separators.iter().find(|&&index| index >= pos) {
+            Some(&index) => pos = index + sep.len(),
+            None => break,
+        }
+    }
+
+    return Ok(ranges);
+}
+
+/// Ищет закрывающую кавычку, начиная с байта `from`, пропуская удвоенные кавычки `""`.
+fn closing_quote(string: &str, from: usize) -> Option<usize> {
+    let bytes = string.as_bytes();
+    let mut index = from;
+
+    while index < bytes.len() {
+        if bytes[index] == b'"' {
+            if bytes.get(index + 1) != Some(&b'"') {
+                return Some(index);
+            }
+
+            index += 1;
+        }
+
+        index += 1;
+    }
+
+    return None;
+}
+
+/// Если часть строки целиком заключена в кавычки, возвращает ее содержимое,
+/// заменив `""` на `"`.
+fn unquote(part: &str) -> Option<String> {
+    let part = part.trim();
+
+    if part.len() < 2 || !part.starts_with('"') || !part.ends_with('"') {
+        return None;
+    }
+
+    return Some(part[1..part.len() - 1].replace("\"\"", "\""));
+}
+
+/// Приводит оригинал и все варианты перевода к нормальной форме NFC.
+#[cfg(feature = "unicode-normalization")]
+fn normalize_nfc(mut text: Text) -> Text {
+    use unicode_normalization::UnicodeNormalization;
+
+    text.original = text.original.nfc().collect();
+    text.translate = text.translate.nfc().collect();
+
+    for translate in text.translates.iter_mut() {
+        *translate = translate.nfc().collect();
+    }
+
+    return text;
+}
+
+/// Ищет в каждом поле тексты с одинаковым оригиналом и добавляет о них предупреждения
+/// [`WarningKind::DuplicateOriginal`] с номерами обеих строк и обоими переводами.
+fn find_duplicate_originals(response: &mut Response) {
+    for field in response.fields.iter() {
+        let mut seen: HashMap<&str, &Text> = Default::default();
+
+        for text in field.content.iter() {
+            let Some(first) = seen.get(text.original.as_str()) else {
+                seen.insert(&text.original, text);
+                continue;
+            };
+
+            response.warnings.push(Warning {
+                location: Location::new(text.line),
+                kind: WarningKind::DuplicateOriginal,
+                message: format!(
+                    "оригинал `{}` уже встречался в строке {} с переводом `{}`, здесь перевод `{}`",
+                    text.original, first.line, first.translate, text.translate
+                ),
+                file: None,
+            });
+        }
+    }
+}
+
+/// Возвращает символы текста, которые не подходят под шаблон допустимого символа
+/// (без повторов, в порядке появления).
+fn find_stray_chars(text: &str, allowed: &Regex) -> String {
+    let mut stray = String::new();
+    let mut buffer = [0; 4];
+
+    for ch in text.chars() {
+        if !allowed.is_match(ch.encode_utf8(&mut buffer)) && !stray.contains(ch) {
+            stray.push(ch);
+        }
+    }
+
+    return stray;
+}
+
+/// Заменяет `from` на `to`. Если `from` в тексте нет, возвращает текст без копирования.
+fn replace_cow<'a>(text: Cow<'a, str>, from: &str, to: &str) -> Cow<'a, str> {
+    if !text.contains(from) {
+        return text;
+    }
+
+    return Cow::Owned(text.replace(from, to));
+}
+
+/// Подставляет значения переменных `@define` вместо `${ИМЯ}`.
+///
+/// Неизвестная переменная остается в тексте, а ее имя добавляется в `undefined`.
+/// `\${` записывается как `${` без подстановки, `${` без закрывающей `}` остается как есть.
+fn expand_variables<'a>(
+    text: Cow<'a, str>,
+    variables: &HashMap<String, String>,
+    undefined: &mut Vec<String>,
+) -> Cow<'a, str> {
+    if !text.contains("${") {
+        return text;
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest: &str = &text;
+
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('\\') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        result.push_str(&rest[..start]);
+
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let name = &rest[start + 2..start + len];
+
+        match variables.get(name) {
+            Some(value) => result.push_str(value),
+            None => {
+                undefined.push(name.to_string());
+                result.push_str(&rest[start..=start + len]);
+            }
+        }
+
+        rest = &rest[start + len + 1..];
+    }
+
+    result.push_str(rest);
+
+    return Cow::Owned(result);
+}
+
+/// Схлопывает любые последовательности пробельных символов в один пробел
+/// и обрезает пробелы по краям.
+fn collapse_whitespace(text: &str) -> String {
+    return text.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+/// Заменяет типографские символы на ASCII-аналоги по таблице [`TYPOGRAPHY_TABLE`].
+fn normalize_typography(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match TYPOGRAPHY_TABLE.iter().find(|(from, _)| *from == ch) {
+            Some((_, to)) => result.push_str(to),
+            None => result.push(ch),
+        }
+    }
+
+    return result;
+}
+
+/// Разбивает перевод на альтернативные варианты по разделителю `alt_sep`.
+/// Пустые варианты отбрасываются, остальные обрезаются по краям.
+fn split_alternatives(translate: &str, alt_sep: &str) -> Vec<String> {
+    return translate
+        .split(alt_sep)
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect();
+}
+
+/// Описывает функцию, которая добавляет в объект-ответ новый элемент [`Field`], если в нём нет такого же набора тэгов.
+/// Если же есть, то добавляет к нему содержимое из переданного вектора [`Field::content`].
+/// Переданные тексты и заметки переносятся без копирования, векторы остаются пустыми.
+/// Набор тэгов остается активным, поэтому копируется, но только при создании нового поля.
+///
+/// Пустое содержимое добавляется только при `keep_empty` и непустом наборе тэгов.
+fn update_response(
+    response: &mut Response,
+    content: &mut Vec<Text>,
+    tags: &HashSet<Tag>,
+    comments: &mut Vec<String>,
+    languages: &Option<Languages>,
+    keep_empty: bool,
+) {
+    if !content.is_empty() || !comments.is_empty() || (keep_empty && !tags.is_empty()) {
+        for field in response.fields.iter_mut() {
+            if *tags == field.tags && *languages == field.languages {
+                field.content.append(content);
+                field.comments.append(comments);
+                return;
+            }
+        }
+
+        response.fields.push(Field {
+            tags: tags.clone(),
+            content: mem::take(content),
+            comments: mem::take(comments),
+            languages: languages.clone(),
+        });
+    }
+}
+
+/// Добавляет в объект-ответ готовое поле (из другого результата или включенного файла)
+/// так же, как [`update_response`], но без копирования: если поля с тем же набором тэгов
+/// и языками нет, поле добавляется целиком.
+fn merge_field(response: &mut Response, mut field: Field, keep_empty: bool) {
+    let keep = !field.content.is_empty()
+        || !field.comments.is_empty()
+        || (keep_empty && !field.tags.is_empty());
+
+    if !keep {
+        return;
+    }
+
+    for existing in response.fields.iter_mut() {
+        if existing.tags == field.tags && existing.languages == field.languages {
+            existing.content.append(&mut field.content);
+            existing.comments.append(&mut field.comments);
+            return;
+        }
+    }
+
+    response.fields.push(field);
+}
+
+/// Вычитает из набора тэгов набор тэгов, которые должны быть вычеркнуты,
+/// и убирает их из порядка открытия `order`.
+fn substract_tags(
+    target_tags: &mut HashSet<Tag>,
+    order: &mut Vec<(Tag, u32)>,
+    tags_to_substract: &[Tag],
+) {
+    for tag in tags_to_substract.iter() {
+        target_tags.remove(tag);
+    }
+
+    order.retain(|(tag, _)| !tags_to_substract.contains(tag));
+}
+
+/// Добавляет в набор тэгов набор тэгов, которые должны быть добавлены.
+/// Новые тэги запоминаются в `order` в порядке открытия вместе с номером строки `line`.
+fn extend_tags(
+    target_tags: &mut HashSet<Tag>,
+    order: &mut Vec<(Tag, u32)>,
+    additional_tags: &[Tag],
+    line: u32,
+) {
+    for tag in additional_tags.iter() {
+        if target_tags.insert(tag.clone()) {
+            order.push((tag.clone(), line));
+        }
+    }
+}
+
+/// Убирает из набора тэгов последний открытый тэг (директива `##` без имени).
+fn pop_tag(target_tags: &mut HashSet<Tag>, order: &mut Vec<(Tag, u32)>) {
+    if let Some((tag, _)) = order.pop() {
+        target_tags.remove(&tag);
+    }
+}
+
+/// Запись директив тэгов, построенная из [`ParseOptions::tag_prefix`]
+/// и [`ParseOptions::bulk_tag_prefix`].
+///
+/// Закрывающая директива получается удвоением первого символа префикса:
+/// `#` -> `##`, `@tags` -> `@@tags`, `$` -> `$$`.
+struct TagSyntax {
+    open: String,
+    close: String,
+    bulk_open: String,
+    bulk_close: String,
+    tags_reg: Regex,
+    remove_tags_reg: Regex,
+}
+
+impl TagSyntax {
+    fn new(options: &ParseOptions) -> TagSyntax {
+        let open = match options.tag_prefix.is_empty() {
+            true => DEFAULT_TAG_PREFIX,
+            false => options.tag_prefix.as_str(),
+        };
+
+        let bulk_open = match options.bulk_tag_prefix.is_empty() {
+            true => DEFAULT_BULK_TAG_PREFIX,
+            false => options.bulk_tag_prefix.as_str(),
+        };
+
+        let close = close_prefix(open);
+        let bulk_close = close_prefix(bulk_open);
+
+        let (single, single_close) = (regex::escape(open), regex::escape(&close));
+        let (bulk, bulk_closing) = (regex::escape(bulk_open), regex::escape(&bulk_close));
+
+        return TagSyntax {
+            tags_reg: Regex::new(&format!(
+                r"(^({single_close}|{single})\w+)|(^{single_close}$)|(^({bulk_closing}|{bulk}))"
+            ))
+            .unwrap(),
+            remove_tags_reg: Regex::new(&format!(r"^({single_close})|({bulk_closing}(\s|$))"))
+                .unwrap(),
+            open: open.to_string(),
+            close,
+            bulk_open: bulk_open.to_string(),
+            bulk_close,
+        };
+    }
+
+    /// Определяет список тэгов из строки. Если строка начинается с `@tags` (или `@@tags`),
+    /// то разбивает остаток строки на тэги, разделенные запятыми, и возвращает их в порядке
+    /// записи без повторов. Если строка начинается с `#` (или `##`), то возвращает одну строку
+    /// без префикса (или пустой список для `##` без имени).
+    fn parse_tags(&self, string: &str) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Default::default();
+
+        let bulk = string
+            .strip_prefix(&self.bulk_close)
+            .or_else(|| string.strip_prefix(&self.bulk_open));
+
+        if let Some(raw) = bulk {
+            for tag in raw.split(",").filter_map(Tag::new) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        } else if let Some(tag) = string
+            .strip_prefix(&self.close)
+            .or_else(|| string.strip_prefix(&self.open))
+            .and_then(Tag::new)
+        {
+            tags.push(tag);
+        }
+
+        return tags;
+    }
+}
+
+/// Возвращает закрывающую директиву для префикса тэга: префикс с удвоенным первым символом.
+fn close_prefix(prefix: &str) -> String {
+    return match prefix.chars().next() {
+        Some(first) => format!("{first}{prefix}"),
+        None => String::new(),
+    };
+}
+
+/// Приводит тэги к каноническому виду (см. [`Tag::canonical`]).
+/// Повторы, появившиеся после замены, убираются.
+fn canonical_tags(tags: Vec<Tag>, options: &ParseOptions) -> Vec<Tag> {
+    if options.tag_aliases.is_empty() && !options.case_insensitive_tags {
+        return tags;
+    }
+
+    let mut canonical: Vec<Tag> = Default::default();
+
+    for tag in tags {
+        let tag = tag.canonical(options);
+
+        if !canonical.contains(&tag) {
+            canonical.push(tag);
+        }
+    }
+
+    return canonical;
+}
+
+/// Количество строк с текстом, по которым определяется разделитель
+/// при [`ParseOptions::auto_detect_separator`].
+const SEPARATOR_SAMPLE_SIZE: usize = 20;
+
+/// Наибольшее количество строк, которое читается заранее, до начала разбора:
+/// при поиске директивы `@sep auto` и при определении разделителя.
+/// Прочитанные строки хранятся в памяти, поэтому их количество ограничено.
+const SEPARATOR_SCAN_LINES: usize = 1000;
+
+/// Разделители, из которых выбирается подходящий при [`ParseOptions::auto_detect_separator`].
+const SEPARATOR_CANDIDATES: [&str; 3] = ["\t", "=", ":"];
+
+/// Определяет разделитель по первым строкам с текстом.
+///
+/// Прочитанные строки сохраняются в `sampled`, чтобы затем их можно было разобрать
+/// как обычно; строки, которые уже есть в `sampled`, тоже учитываются. Выбирается
+/// разделитель, который делит на две части больше половины строк и чаще остальных.
+/// Если такого нет, разделители одинаково подходят или в начале файла есть директива
+/// `@sep` (кроме `@sep auto`), возвращается `None`. Всего читается не больше
+/// [`SEPARATOR_SCAN_LINES`] строк.
+fn detect_separator<I: Iterator<Item = Line>>(
+    lines: &mut I,
+    sampled: &mut Vec<Line>,
+) -> Option<String> {
+    let mut samples: Vec<String> = Default::default();
+    let mut read = 0;
+
+    while samples.len() < SEPARATOR_SAMPLE_SIZE && read < SEPARATOR_SCAN_LINES {
+        // Сначала разбираются строки, которые уже были прочитаны раньше.
+        if read == sampled.len() {
+            let Some(line) = lines.next() else {
+                break;
+            };
+
+            sampled.push(line);
+        }
+
+        let string = match &sampled[read].text {
+            Ok(x) => x.trim().to_string(),
+            // После такой ошибки разбор прерывается, поэтому читать дальше незачем.
+            Err(err) if err.kind() != io::ErrorKind::InvalidData => break,
+            Err(_) => "".to_string(),
+        };
+
+        read += 1;
+
+        let declared = separator_directive(&string).is_some_and(|value| !is_auto_separator(value));
+
+        if samples.is_empty() && declared {
+            return None;
+        }
+
+        let is_text = !skip_line_else(&string) && !string.starts_with(['#', '@']);
+
+        if is_text {
+            samples.push(string);
+        }
+    }
+
+    let mut scores: Vec<(usize, &str)> = SEPARATOR_CANDIDATES
+        .iter()
+        .map(|sep| {
+            (
+                samples
+                    .iter()
+                    .filter(|x| x.matches(sep).count() == 1)
+                    .count(),
+                *sep,
+            )
+        })
+        .collect();
+
+    scores.sort_by_key(|&(score, _)| Reverse(score));
+
+    let (best, sep) = scores[0];
+
+    if best * 2 <= samples.len() || best == scores[1].0 {
+        return None;
+    }
+
+    return Some(sep.to_string());
+}
+
+/// Создает предупреждение о директиве `@sep`, которая не действует: разделитель уже
+/// задан предыдущей директивой или директива стоит после начала текста.
+fn ignored_separator_warning(line: u32) -> Warning {
+    return Warning {
+        location: Location::new(line),
+        kind: WarningKind::MisplacedDirective,
+        message: "директива `@sep` действует только один раз и только в начале файла, \
+                  эта директива пропущена"
+            .to_string(),
+        file: None,
+    };
+}
+
+/// Создает предупреждение о директиве `@lang` с неверным количеством языков.
+fn invalid_language_warning(line: u32) -> Warning {
+    return Warning {
+        location: Location::new(line),
+        kind: WarningKind::InvalidDirective,
+        message: "директива `@lang` должна содержать два языка: `@lang <оригинал> <перевод>`"
+            .to_string(),
+        file: None,
+    };
+}
+
+/// Проверяет, что разделитель не совпадает с префиксами тэгов и маркерами комментариев
+/// и не начинается с них (или они с него). Иначе строки с таким разделителем
+/// разбирались бы как директивы или комментарии.
+fn check_separator_conflicts(
+    sep: &str,
+    syntax: &TagSyntax,
+    inline_comment: Option<&str>,
+) -> Result<(), ParseError> {
+    let markers = [
+        Some(syntax.open.as_str()),
+        Some(syntax.close.as_str()),
+        Some(syntax.bulk_open.as_str()),
+        Some(syntax.bulk_close.as_str()),
+        Some("//"),
+        inline_comment,
+    ];
+
+    let conflicts = markers
+        .into_iter()
+        .flatten()
+        .any(|marker| sep.starts_with(marker) || marker.starts_with(sep));
+
+    if conflicts {
+        return Err(ParseError::InvalidSeparator(sep.to_string()));
+    }
+
+    return Ok(());
+}
+
+/// Проверяет значение директивы `@sep` и возвращает разделитель без пробелов по краям.
+///
+/// Разделитель в двойных кавычках (`@sep " -> "`) берется как есть, вместе с пробелами.
+/// Если после `@sep` ничего нет, указаны только пробельные символы или пустые кавычки,
+/// возвращается [`ParseError::InvalidSeparator`].
+fn validate_separator(value: &str) -> Result<String, ParseError> {
+    let quoted = unquote(value);
+    let sep = match &quoted {
+        Some(x) => x.as_str(),
+        None => value.trim(),
+    };
+
+    if sep.is_empty() {
+        return Err(ParseError::InvalidSeparator(value.to_string()));
+    }
+
+    return Ok(sep.to_string());
+}
+
+/// Читает строки начала файла (пустые, комментарии и директивы `@sep`, `@charset`, `@lang`)
+/// до первой директивы `@sep` или первой другой строки и проверяет, что это `@sep auto`.
+/// Прочитанные строки сохраняются в `sampled`. Читается не больше
+/// [`SEPARATOR_SCAN_LINES`] строк.
+fn requests_auto_separator<I: Iterator<Item = Line>>(
+    lines: &mut I,
+    sampled: &mut Vec<Line>,
+) -> bool {
+    for line in lines.by_ref().take(SEPARATOR_SCAN_LINES) {
+        let string = match &line.text {
+            Ok(x) => x.trim().to_string(),
+            Err(err) if err.kind() != io::ErrorKind::InvalidData => {
+                sampled.push(line);
+                return false;
+            }
+            Err(_) => "".to_string(),
+        };
+
+        sampled.push(line);
+
+        if let Some(value) = separator_directive(&string) {
+            return is_auto_separator(value);
+        }
+
+        let preamble = skip_line_else(&string)
+            || charset_directive(&string).is_some()
+            || language_directive(&string).is_some();
+
+        if !preamble {
+            return false;
+        }
+    }
+
+    return false;
+}
+
+/// Проверяет, что значение директивы `@sep` - это `auto` (без кавычек).
+fn is_auto_separator(value: &str) -> bool {
+    return value.trim() == "auto";
+}
+
+/// Если строка является директивой `@sep`, возвращает ее значение (все, что после `@sep`).
+fn separator_directive(string: &str) -> Option<&str> {
+    const DERECTIVE: &str = "@sep";
+
+    return match string.strip_prefix(DERECTIVE) {
+        Some(value) if value.is_empty() || value.starts_with(char::is_whitespace) => Some(value),
+        _ => None,
+    };
+}
+
+/// Если строка является директивой `@charset <кодировка>`, возвращает кодировку из нее.
+fn charset_directive(string: &str) -> Option<&str> {
+    const DERECTIVE: &str = "@charset";
+
+    return match string.strip_prefix(DERECTIVE) {
+        Some(value) if value.starts_with(char::is_whitespace) => Some(value.trim()),
+        _ => None,
+    };
+}
+
+/// Строка, прочитанная [`LineReader`].
+struct Line {
+    /// Текст строки без `\n` (или `\r\n`) либо ошибка чтения.
+    text: io::Result<String>,
+    /// Сколько байтов источника заняла строка вместе с переводом строки.
+    bytes: usize,
+    /// Длина строки в байтах, если она длиннее [`ParseOptions::max_line_len`]. Такая
+    /// строка не сохраняется, а `text` остается пустым.
+    too_long: Option<usize>,
+}
+
+/// Построчное чтение источника с подсчетом прочитанных байтов.
+///
+/// Если задано [`ParseOptions::max_bytes`], из источника читается не больше `max_bytes + 1`
+/// байтов, поэтому превышение ограничения обнаруживается без чтения остатка строки.
+/// Так же строка длиннее [`ParseOptions::max_line_len`] читается только до ограничения,
+/// а ее остаток пропускается.
+/// Строка в неверном UTF-8 возвращается как ошибка [`io::ErrorKind::InvalidData`].
+///
+/// С опцией `encoding` строки перекодируются в UTF-8 из кодировки, заданной директивой
+/// `@charset` в первой строке или [`ParseOptions::encoding`], а недопустимые
+/// последовательности заменяются символом `U+FFFD`. Поддерживаются только кодировки,
+/// совместимые с ASCII, так как строки разделяются по байту `\n`.
+struct LineReader<R> {
+    reader: R,
+    max_bytes: Option<usize>,
+    max_line_len: Option<usize>,
+    bytes_read: usize,
+    too_long: Option<usize>,
+    #[cfg(feature = "encoding")]
+    encoding: &'static encoding_rs::Encoding,
+    #[cfg(feature = "encoding")]
+    first: Option<(Vec<u8>, usize)>,
+}
+
+impl<R: BufRead> LineReader<R> {
+    #[cfg(not(feature = "encoding"))]
+    fn new(reader: R, options: &ParseOptions) -> Result<Self, ParseError> {
+        return Ok(LineReader {
+            reader,
+            max_bytes: options.max_bytes,
+            max_line_len: options.max_line_len,
+            bytes_read: 0,
+            too_long: None,
+        });
+    }
+
+    /// Читает первую строку и выбирает кодировку: из директивы `@charset` в ней,
+    /// из [`ParseOptions::encoding`] или UTF-8.
+    #[cfg(feature = "encoding")]
+    fn new(reader: R, options: &ParseOptions) -> Result<Self, ParseError> {
+        let mut lines = LineReader {
+            reader,
+            max_bytes: options.max_bytes,
+            max_line_len: options.max_line_len,
+            bytes_read: 0,
+            too_long: None,
+            encoding: encoding_rs::UTF_8,
+            first: None,
+        };
+
+        let mut first = Vec::new();
+        let bytes = lines.read_raw_line(&mut first)?;
+
+        let header = String::from_utf8_lossy(&first);
+        let label = charset_directive(header.trim()).or(options.encoding.as_deref());
+
+        if let Some(label) = label {
+            lines.encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+                .filter(|encoding| encoding.is_ascii_compatible())
+                .ok_or_else(|| ParseError::UnknownEncoding(label.to_string()))?;
+        }
+
+        lines.first = (bytes > 0).then_some((first, bytes));
+
+        return Ok(lines);
+    }
+
+    /// Читает строку в `buf` без завершающего `\n` (или `\r\n`) и возвращает количество
+    /// прочитанных байтов; 0 означает, что источник закончился. Если строка длиннее
+    /// [`ParseOptions::max_line_len`], `buf` остается пустым, а длина строки
+    /// сохраняется в `too_long`.
+    fn read_raw_line(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut limit = match self.max_bytes {
+            Some(max) => max.saturating_sub(self.bytes_read).saturating_add(1),
+            None => usize::MAX,
+        };
+
+        // Допустимая строка вместе с `\r\n` занимает не больше `max_line_len + 2` байтов.
+        if let Some(max) = self.max_line_len {
+            limit = limit.min(max.saturating_add(2));
+        }
+
+        let mut read = io::Read::take(&mut self.reader, limit as u64).read_until(b'\n', buf)?;
+        let newline = buf.ends_with(b"\n");
+
+        if newline {
+            buf.pop();
+
+            if buf.ends_with(b"\r") {
+                buf.pop();
+            }
+        }
+
+        if self.max_line_len.is_some_and(|max| buf.len() > max) {
+            let len = match newline {
+                true => buf.len(),
+                false => self.skip_line(buf, &mut read)?,
+            };
+
+            buf.clear();
+            self.too_long = Some(len);
+        }
+
+        self.bytes_read += read;
+
+        return Ok(read);
+    }
+
+    /// Пропускает остаток строки до `\n` включительно, не сохраняя его, и возвращает
+    /// длину всей строки без перевода строки. `start` - уже прочитанное начало строки,
+    /// а к `read` прибавляются пропущенные байты. Пропуск прекращается, как только
+    /// превышено [`ParseOptions::max_bytes`].
+    fn skip_line(&mut self, start: &[u8], read: &mut usize) -> io::Result<usize> {
+        let mut len = start.len();
+        let mut last = start.last().copied();
+
+        while self
+            .max_bytes
+            .is_none_or(|max| self.bytes_read + *read <= max)
+        {
+            let chunk = self.reader.fill_buf()?;
+
+            if chunk.is_empty() {
+                break;
+            }
+
+            let newline = chunk.iter().position(|&byte| byte == b'\n');
+            let content = &chunk[..newline.unwrap_or(chunk.len())];
+
+            len += content.len();
+            last = content.last().copied().or(last);
+
+            let used = newline.map_or(chunk.len(), |pos| pos + 1);
+            self.reader.consume(used);
+            *read += used;
+
+            if newline.is_some() {
+                if last == Some(b'\r') {
+                    len -= 1;
+                }
+
+                break;
+            }
+        }
+
+        return Ok(len);
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    fn decode(&self, bytes: Vec<u8>) -> io::Result<String> {
+        return String::from_utf8(bytes).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        });
+    }
+
+    #[cfg(feature = "encoding")]
+    fn decode(&self, bytes: Vec<u8>) -> io::Result<String> {
+        let (text, _) = self.encoding.decode_without_bom_handling(&bytes);
+
+        return Ok(text.into_owned());
+    }
+}
+
+impl<R: BufRead> Iterator for LineReader<R> {
+    type Item = Line;
+
+    fn next(&mut self) -> Option<Line> {
+        #[cfg(feature = "encoding")]
+        if let Some((first, bytes)) = self.first.take() {
+            return Some(Line {
+                text: self.decode(first),
+                bytes,
+                too_long: self.too_long.take(),
+            });
+        }
+
+        let mut buf = Vec::new();
+
+        let bytes = match self.read_raw_line(&mut buf) {
+            Ok(0) => return None,
+            Ok(x) => x,
+            Err(err) => {
+                return Some(Line {
+                    text: Err(err),
+                    bytes: 0,
+                    too_long: None,
+                })
+            }
+        };
+
+        return Some(Line {
+            text: self.decode(buf),
+            bytes,
+            too_long: self.too_long.take(),
+        });
+    }
+}
+
+/// Проверяет, что путь относительный и не выходит за пределы каталога, от которого
+/// он отсчитывается (не содержит `..`).
+fn is_nested_path(path: &Path) -> bool {
+    return path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+}
+
+/// Если строка является директивой `@include <путь>`, возвращает путь из нее.
+fn include_directive(string: &str) -> Option<&str> {
+    const DERECTIVE: &str = "@include";
+
+    return match string.strip_prefix(DERECTIVE) {
+        Some(value) if value.starts_with(char::is_whitespace) && !value.trim().is_empty() => {
+            Some(value.trim())
+        }
+        _ => None,
+    };
+}
+
+/// Если строка является директивой `@comment <заметка>`, возвращает заметку из нее.
+fn comment_directive(string: &str) -> Option<&str> {
+    const DERECTIVE: &str = "@comment";
+
+    return match string.strip_prefix(DERECTIVE) {
+        Some(value) if value.starts_with(char::is_whitespace) && !value.trim().is_empty() => {
+            Some(value.trim())
+        }
+        _ => None,
+    };
+}
+
+/// Если строка является директивой `@define ИМЯ=значение`, возвращает имя и значение из нее.
+/// Для директивы без `=` или с неверным именем возвращает `Some(None)`.
+fn define_directive(string: &str) -> Option<Option<(&str, &str)>> {
+    const DERECTIVE: &str = "@define";
+
+    let value = match string.strip_prefix(DERECTIVE) {
+        Some(value) if value.is_empty() || value.starts_with(char::is_whitespace) => value,
+        _ => return None,
+    };
+
+    let Some((name, value)) = value.split_once('=') else {
+        return Some(None);
+    };
+
+    let name = name.trim();
+
+    if name.is_empty() || !name.chars().all(|x| x.is_alphanumeric() || x == '_') {
+        return Some(None);
+    }
+
+    return Some(Some((name, value.trim())));
+}
+
+/// Если строка является директивой `@lang <оригинал> <перевод>`, возвращает языки из нее.
+/// Для директивы с неверным количеством языков возвращает `Some(None)`.
+fn language_directive(string: &str) -> Option<Option<Languages>> {
+    const DERECTIVE: &str = "@lang";
+
+    let value = match string.strip_prefix(DERECTIVE) {
+        Some(value) if value.is_empty() || value.starts_with(char::is_whitespace) => value,
+        _ => return None,
+    };
+
+    return match value.split_whitespace().collect::<Vec<&str>>()[..] {
+        [original, translate] => Some(Some(Languages {
+            original: original.to_string(),
+            translate: translate.to_string(),
+        })),
+        _ => Some(None),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Парсит текст с настройками по умолчанию.
+    fn parse_text(input: &str) -> Box<Response> {
+        return parse_str(input, &ParseOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn translate_index_lists_originals_of_shared_translate() {
+        let response = parse_text("Hallo -- Привет\nServus -- Привет\nTschüss -- Пока\nLeer\n");
+        let index = response.translate_index();
+
+        assert_eq!(index["Привет"], vec!["Hallo", "Servus"]);
+        assert_eq!(index["Пока"], vec!["Tschüss"]);
+        assert!(!index.contains_key(""));
+    }
+
+    /// Парсит текст с настройками `options`.
+    fn parse_with(input: &str, options: ParseOptions) -> Box<Response> {
+        return parse_str(input, &options).unwrap();
+    }
+
+    #[test]
+    fn single_translation_is_unchanged_with_alternatives_separator() {
+        let options = ParseOptions::builder().alternatives_separator("|").build();
+        let response = parse_with("Hello -- Hallo\n", options);
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.translate, "Hallo");
+        assert_eq!(text.translates, vec!["Hallo"]);
+    }
+
+    #[test]
+    fn alternatives_are_split_and_trimmed() {
+        let options = ParseOptions::builder().alternatives_separator("|").build();
+        let response = parse_with("Hello -- Hallo |  Guten Tag | Servus \n", options);
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.translate, "Hallo");
+        assert_eq!(text.translates, vec!["Hallo", "Guten Tag", "Servus"]);
+    }
+
+    #[test]
+    fn alternatives_separator_is_off_by_default() {
+        let response = parse_text("Hello -- Hallo und Tag\n");
+
+        assert!(response.fields[0].content[0].translates.is_empty());
+    }
+
+    #[test]
+    fn sep_offset_counts_chars_in_cyrillic_line() {
+        let options = ParseOptions::builder().track_sep_offset(true).build();
+        let response = parse_with("Привет мир -- Hallo Welt\n", options);
+
+        assert_eq!(response.fields[0].content[0].sep_offset, Some(11));
+    }
+
+    #[test]
+    fn sep_offset_is_not_tracked_by_default() {
+        let response = parse_text("Привет -- Hallo\n");
+
+        assert_eq!(response.fields[0].content[0].sep_offset, None);
+    }
+
+    #[test]
+    fn typography_is_normalized_in_original_and_translate() {
+        let options = ParseOptions::builder().normalize_typography(true).build();
+        let response = parse_with("“Hallo” – it’s — here -- «Привет» – ‘да’\n", options);
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.original, "\"Hallo\" - it's -- here");
+        assert_eq!(text.translate, "«Привет» - 'да'");
+    }
+
+    #[test]
+    fn typography_is_kept_by_default() {
+        let response = parse_text("“Hallo” -- Привет\n");
+
+        assert_eq!(response.fields[0].content[0].original, "“Hallo”");
+    }
+
+    /// Возвращает имена тэгов поля в алфавитном порядке.
+    fn tag_names(field: &Field) -> Vec<&str> {
+        let mut tags: Vec<&str> = field.tags.iter().map(Tag::as_str).collect();
+        tags.sort();
+
+        return tags;
+    }
+
+    /// Создает временный каталог с файлами `files` (путь относительно каталога и текст).
+    fn temp_dir_with(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+
+        for (name, text) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+
+        return dir;
+    }
+
+    #[test]
+    fn parse_dir_merges_fields_with_same_tags() {
+        let dir = temp_dir_with(&[
+            ("a.txt", "#common\nEins -- Один\n"),
+            (
+                "b.txt",
+                "#common\nZwei -- Два\nBöse <tag> -- Плохо\n##common\n#only_b\nDrei -- Три\n",
+            ),
+            ("notes.md", "Vier -- Четыре\n"),
+            ("nested/c.txt", "#common\nFünf -- Пять\n"),
+        ]);
+
+        let response = parse_dir(dir.path(), false, &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.fields.len(), 2);
+        assert_eq!(tag_names(&response.fields[0]), vec!["common"]);
+
+        let originals: Vec<&str> = response.fields[0]
+            .content
+            .iter()
+            .map(|text| text.original.as_str())
+            .collect();
+
+        assert_eq!(originals, vec!["Eins", "Zwei"]);
+        assert_eq!(tag_names(&response.fields[1]), vec!["only_b"]);
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].file.as_deref(), Some("b.txt"));
+    }
+
+    #[test]
+    fn parse_dir_processes_merged_content() {
+        let dir = temp_dir_with(&[
+            ("a.txt", "#t\nZeta -- З\nAlpha -- А\n"),
+            ("b.txt", "#t\nBeta -- Б\nAlpha -- А\nAlpha -- Альфа\n"),
+        ]);
+        let options = ParseOptions::builder()
+            .dedup_content(true)
+            .sort_content(true)
+            .detect_duplicates(true)
+            .build();
+
+        let response = parse_dir(dir.path(), false, &options).unwrap();
+        let originals: Vec<&str> = response.fields[0]
+            .content
+            .iter()
+            .map(|text| text.original.as_str())
+            .collect();
+
+        assert_eq!(originals, vec!["Alpha", "Alpha", "Beta", "Zeta"]);
+        assert_eq!(response.fields[0].content[1].translate, "Альфа");
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::DuplicateOriginal);
+
+        #[cfg(feature = "parallel")]
+        {
+            let parallel = parse_dir_parallel(dir.path(), false, &options).unwrap();
+
+            assert_eq!(pairs(&parallel), pairs(&response));
+            assert_eq!(parallel.warnings.len(), 1);
+        }
+    }
+
+    #[test]
+    fn included_duplicates_are_reported_once() {
+        let dir = temp_dir_with(&[
+            ("main.txt", "Eins -- Один\n@include part.txt\n"),
+            ("part.txt", "Eins -- Раз\nZwei -- Два\nZwei -- Двa\n"),
+        ]);
+        let options = ParseOptions::builder()
+            .allow_include(true)
+            .detect_duplicates(true)
+            .build();
+
+        let response = parse(&dir.path().join("main.txt"), &options).unwrap();
+
+        assert_eq!(response.warnings.len(), 2);
+    }
+
+    #[test]
+    fn parse_dir_recursive_includes_nested_files() {
+        let dir = temp_dir_with(&[
+            ("a.txt", "#common\nEins -- Один\n"),
+            ("nested/c.txt", "#common\nFünf -- Пять\n"),
+        ]);
+
+        let response = parse_dir(dir.path(), true, &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.fields.len(), 1);
+        assert_eq!(response.fields[0].content.len(), 2);
+    }
+
+    #[test]
+    fn error_file_is_set_by_parse_path() {
+        let dir = temp_dir_with(&[("broken.txt", "Gut -- Хорошо\nBöse <tag> -- Плохо\n")]);
+        let path = dir.path().join("broken.txt");
+
+        let response = parse(&path, &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(
+            response.errors[0].file.as_deref(),
+            Some(path.display().to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn error_file_is_empty_for_parse_str() {
+        let response = parse_text("Böse <tag> -- Плохо\n");
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].file, None);
+    }
+
+    #[test]
+    fn chunk_keeps_fields_whole_and_respects_limit() {
+        let response = parse_text(
+            "@lang de ru\n#a\nEins -- Один\nZwei -- Два\n##a\n#b\nDrei -- Три\nVier -- Четыре\nFünf -- Пять\n##b\n#c\nSechs -- Шесть\nSieben -- Семь\nAcht -- Восемь\nNeun -- Девять\nZehn -- Десять\n",
+        );
+
+        let chunks = response.chunk(3);
+        let sizes: Vec<usize> = chunks.iter().map(|chunk| chunk.total_texts()).collect();
+
+        assert_eq!(sizes, vec![2, 3, 3, 2]);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.languages == response.languages));
+        assert_eq!(tag_names(&chunks[0].fields[0]), vec!["a"]);
+        assert_eq!(tag_names(&chunks[1].fields[0]), vec!["b"]);
+        assert_eq!(tag_names(&chunks[2].fields[0]), vec!["c"]);
+        assert_eq!(tag_names(&chunks[3].fields[0]), vec!["c"]);
+    }
+
+    #[test]
+    fn chunk_packs_small_fields_together() {
+        let response = parse_text("#a\nEins -- Один\n##a\n#b\nZwei -- Два\n##b\n#c\nDrei -- Три\n");
+        let chunks = response.chunk(2);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].fields.len(), 2);
+        assert_eq!(chunks[1].fields.len(), 1);
+    }
+
+    #[test]
+    fn stats_counts_fields_texts_and_errors() {
+        let response = parse_text(
+            "#a\nEins -- Один\nZwei\n##a\n@tags a, b\nDrei -- Три\nBöse <tag> -- Плохо\n@@tags a, b\nVier\n",
+        );
+        let stats = response.stats();
+
+        assert_eq!(stats.fields, 3);
+        assert_eq!(stats.texts, 4);
+        assert_eq!(stats.empty_translations, 2);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(
+            stats.tags,
+            BTreeSet::from(["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(stats.tag_texts["a"], 3);
+        assert_eq!(stats.tag_texts["b"], 1);
+        assert_eq!(stats.top_tags(1), vec![("a", 3)]);
+    }
+
+    #[test]
+    fn whitespace_is_collapsed_only_in_original() {
+        let options = ParseOptions::builder()
+            .normalize_original_whitespace_only(true)
+            .build();
+        let response = parse_with("Guten   \t Tag  Welt -- Добрый   день\n", options);
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.original, "Guten Tag Welt");
+        assert_eq!(text.translate, "Добрый   день");
+    }
+
+    #[test]
+    fn escaped_separator_in_original_is_literal() {
+        let response = parse_text("@sep =\na \\= b = Translation\n");
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.original, "a = b");
+        assert_eq!(text.translate, "Translation");
+    }
+
+    #[test]
+    fn escaped_separator_in_translate_is_literal() {
+        let response = parse_text("@sep =\nOriginal = x \\= y\n");
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.original, "Original");
+        assert_eq!(text.translate, "x = y");
+    }
+
+    #[test]
+    fn line_with_only_escaped_separators_is_not_split() {
+        let response = parse_text("@sep =\na \\= b \\= c\n");
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.original, "a = b = c");
+        assert_eq!(text.translate, "");
+    }
+
+    #[test]
+    fn escaped_default_separator_is_not_a_forbidden_char() {
+        let response = parse_text("a \\-- b -- T\n");
+
+        assert!(response.errors.is_empty());
+        assert_eq!(response.fields[0].content[0].original, "a -- b");
+    }
+
+    #[test]
+    fn several_separators_warn_in_last_mode() {
+        let options = ParseOptions::builder()
+            .split_from(SeparatorMode::Last)
+            .build();
+        let response = parse_with("@sep =\na = b = c\n", options);
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::AmbiguousSeparator);
+        assert_eq!(response.warnings[0].location.line, 2);
+        assert_eq!(text.original, "a = b");
+        assert_eq!(text.translate, "c");
+    }
+
+    #[test]
+    fn escaped_separator_does_not_count_as_ambiguous() {
+        let options = ParseOptions::builder()
+            .split_from(SeparatorMode::Last)
+            .build();
+        let response = parse_with("@sep =\na \\= b = c\n", options);
+
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn first_mode_splits_on_first_separator() {
+        let response = parse_text("@sep =\nC++ = C plus plus = note\n");
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.original, "C++");
+        assert_eq!(text.translate, "C plus plus = note");
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn last_mode_splits_on_last_separator() {
+        let options = ParseOptions::builder()
+            .split_from(SeparatorMode::Last)
+            .build();
+        let response = parse_with("@sep =\nC++ = C plus plus = note\n", options);
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.original, "C++ = C plus plus");
+        assert_eq!(text.translate, "note");
+    }
+
+    #[test]
+    fn empty_separator_directive_is_rejected() {
+        for input in [
+            "@sep \nHallo -- Привет\n",
+            "@sep \t\nHallo -- Привет\n",
+            "@sep \"\"\n",
+        ] {
+            let result = parse_str(input, &ParseOptions::default());
+
+            assert!(
+                matches!(result, Err(ParseError::InvalidSeparator(_))),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn empty_separator_option_is_rejected() {
+        let options = ParseOptions::builder().separator("").build();
+
+        assert!(matches!(
+            parse_str("Hallo -- Привет\n", &options),
+            Err(ParseError::InvalidSeparator(_))
+        ));
+    }
+
+    #[test]
+    fn directive_with_open_and_close_is_reported_and_closes() {
+        let response = parse_text("@tags a, b\nEins -- Один\n@tags a, @@tags b\nZwei -- Два\n");
+
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::AmbiguousDirective);
+        assert_eq!(response.warnings[0].location.line, 3);
+        assert_eq!(tag_names(&response.fields[1]), vec!["b"]);
+    }
+
+    #[test]
+    fn ambiguous_directive_can_fail_parsing() {
+        let options = ParseOptions::builder()
+            .fail_on_ambiguous_directive(true)
+            .build();
+
+        assert!(matches!(
+            parse_str("@tags a, @@tags b\n", &options),
+            Err(ParseError::AmbiguousDirective { line: 1 })
+        ));
+    }
+
+    #[test]
+    fn bare_bulk_close_is_not_ambiguous() {
+        let response = parse_text("@tags a\nEins -- Один\n@@tags\nZwei -- Два\n");
+
+        assert!(response.warnings.is_empty());
+        assert_eq!(response.fields.len(), 1);
+        assert_eq!(response.fields[0].content.len(), 2);
+    }
+
+    #[test]
+    fn json_is_identical_between_runs() {
+        let input = "@tags zeta, alpha, mu, beta, omega\nEins -- Один\n@@tags zeta, alpha, mu, beta, omega\n#b\nZwei -- Два\n##b\n#a\nDrei -- Три\n";
+
+        let first = serde_json::to_string(&parse_text(input)).unwrap();
+        let second = serde_json::to_string(&parse_text(input)).unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.contains(r#""tags":["alpha","beta","mu","omega","zeta"]"#));
+    }
+
+    #[test]
+    fn fields_keep_first_seen_order() {
+        let response =
+            parse_text("#b\nZwei -- Два\n##b\n#a\nDrei -- Три\n##a\n#b\nVier -- Четыре\n");
+        let order: Vec<Vec<&str>> = response.fields.iter().map(tag_names).collect();
+
+        assert_eq!(order, vec![vec!["b"], vec!["a"]]);
+        assert_eq!(response.fields[0].content.len(), 2);
+    }
+
+    /// Файл с пустым блоком тэгов `empty` между блоками с текстами.
+    const EMPTY_BLOCK_INPUT: &str = "#a\nEins -- Один\n##a\n#empty\n##empty\n#b\nZwei -- Два\n";
+
+    #[test]
+    fn empty_fields_are_dropped_by_default() {
+        let response = parse_text(EMPTY_BLOCK_INPUT);
+        let order: Vec<Vec<&str>> = response.fields.iter().map(tag_names).collect();
+
+        assert_eq!(order, vec![vec!["a"], vec!["b"]]);
+    }
+
+    #[test]
+    fn empty_fields_are_kept_with_option() {
+        let options = ParseOptions::builder().keep_empty_fields(true).build();
+        let response = parse_with(EMPTY_BLOCK_INPUT, options);
+        let order: Vec<Vec<&str>> = response.fields.iter().map(tag_names).collect();
+
+        assert_eq!(order, vec![vec!["a"], vec!["empty"], vec!["b"]]);
+        assert!(response.fields[1].content.is_empty());
+    }
+
+    #[test]
+    fn skip_line_predicate_excludes_lines_from_fields_and_errors() {
+        let input = "$$meta: <id=1>\nEins -- Один\n  $$ status -- done\nZwei -- Два\n";
+
+        let options = ParseOptions::builder()
+            .skip_line_predicate(|line| line.starts_with("$$"))
+            .build();
+        let response = parse_with(input, options);
+        let originals: Vec<&str> = response
+            .iter_texts()
+            .map(|(_, text)| text.original.as_str())
+            .collect();
+
+        assert_eq!(originals, vec!["Eins", "Zwei"]);
+        assert!(response.errors.is_empty());
+
+        let response = parse_text(input);
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.total_texts(), 3);
+    }
+
+    /// Шаблон допустимого символа для русского текста.
+    fn cyrillic() -> Regex {
+        return Regex::new(r"[\p{Cyrillic}\p{P}\s\d]").unwrap();
+    }
+
+    #[test]
+    fn latin_letter_in_cyrillic_column_is_reported() {
+        let options = ParseOptions::builder()
+            .allowed_chars("ru", cyrillic())
+            .build();
+        let response = parse_with(
+            "@lang ru de\nПривеa мир -- Hallo Welt\nПока -- Tschüss\n",
+            options,
+        );
+
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::DisallowedCharacters);
+        assert_eq!(response.warnings[0].location.line, 2);
+        assert!(response.warnings[0].message.contains('a'));
+        assert_eq!(response.total_texts(), 2);
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn decomposed_accent_is_composed_with_normalize() {
+        let input = "Cafe\u{301} -- Caf\u{65}\u{301} noir\n";
+
+        let options = ParseOptions::builder().normalize(true).build();
+        let response = parse_with(input, options);
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.original, "Caf\u{e9}");
+        assert_eq!(text.translate, "Caf\u{e9} noir");
+
+        let response = parse_text(input);
+
+        assert_eq!(response.fields[0].content[0].original, "Cafe\u{301}");
+    }
+
+    /// Источник, который можно только читать, без [`io::Seek`], как сетевое соединение.
+    struct ReadOnly<R>(R);
+
+    impl<R: io::Read> io::Read for ReadOnly<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            return self.0.read(buf);
+        }
+    }
+
+    #[test]
+    fn parse_reader_honors_preamble_without_seek() {
+        let input = "// Kopf\n\n@lang de ru\n@sep =\n#a\nEins = Один\n";
+        let reader = BufReader::new(ReadOnly(io::Cursor::new(input)));
+
+        let response = parse_reader(reader, &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.languages, Languages::new("de", "ru"));
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+    }
+
+    #[test]
+    fn parse_reader_detects_separator_without_seek() {
+        let input = "@sep auto\nEins\tОдин\nZwei\tДва\n";
+        let reader = BufReader::new(ReadOnly(io::Cursor::new(input)));
+
+        let response = parse_reader(reader, &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.separator, "\t");
+        assert_eq!(response.total_texts(), 2);
+    }
+
+    #[test]
+    fn duplicate_originals_are_reported() {
+        let options = ParseOptions::builder().detect_duplicates(true).build();
+        let response = parse_with(
+            "#a\nHallo -- Привет\nTschüss -- Пока\n Hallo  -- Здравствуй\n",
+            options,
+        );
+
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::DuplicateOriginal);
+        assert_eq!(response.warnings[0].location.line, 4);
+        assert_eq!(
+            response.warnings[0].message,
+            "оригинал `Hallo` уже встречался в строке 2 с переводом `Привет`, здесь перевод `Здравствуй`"
+        );
+    }
+
+    #[test]
+    fn same_original_in_other_field_is_not_a_duplicate() {
+        let options = ParseOptions::builder().detect_duplicates(true).build();
+        let response = parse_with(
+            "#a\nHallo -- Привет\n##a\n#b\nHallo -- Здравствуй\n",
+            options,
+        );
+
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn sep_in_the_middle_of_text_is_not_a_directive() {
+        let response = parse_text("Use @sep here -- Используй @sep здесь\n");
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.original, "Use @sep here");
+        assert_eq!(text.translate, "Используй @sep здесь");
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn only_first_sep_directive_is_honored() {
+        let response = parse_text("@sep =\n@sep ;\nEins = Один\n");
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::MisplacedDirective);
+        assert_eq!(response.warnings[0].location.line, 2);
+    }
+
+    #[test]
+    fn sep_after_leading_comment_is_honored() {
+        let response = parse_text("// Kopf\n\n// noch ein Kommentar\n@sep =\nEins = Один\n");
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.fields[0].content[0].original, "Eins");
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn sep_in_the_middle_of_file_is_ignored_with_warning() {
+        let mut input: String = (1..50)
+            .map(|index| format!("Satz {index} -- Фраза {index}\n"))
+            .collect();
+        input.push_str("@sep =\nEins = Один\n");
+
+        let response = parse_text(&input);
+        let last = response.fields[0].content.last().unwrap();
+
+        assert_eq!(response.separator, "--");
+        assert_eq!(last.original, "Eins = Один");
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::MisplacedDirective);
+        assert_eq!(response.warnings[0].location.line, 50);
+    }
+
+    #[test]
+    fn input_under_limits_is_parsed() {
+        let options = ParseOptions::builder().max_lines(3).max_bytes(100).build();
+        let response = parse_with("Eins -- Один\nZwei -- Два\nDrei -- Три\n", options);
+
+        assert_eq!(response.total_texts(), 3);
+    }
+
+    #[test]
+    fn too_many_lines_fail_at_first_extra_line() {
+        let options = ParseOptions::builder().max_lines(2).build();
+        let result = parse_str("Eins -- Один\nZwei -- Два\nDrei -- Три\n", &options);
+
+        assert!(matches!(result, Err(ParseError::LimitExceeded { line: 3 })));
+    }
+
+    #[test]
+    fn too_many_bytes_fail_at_line_over_limit() {
+        // Каждая строка вместе с переводом строки занимает 10 байт.
+        let options = ParseOptions::builder().max_bytes(25).build();
+        let result = parse_str("Eins -- 1\nZwei -- 2\nDrei -- 3\nVier -- 4\n", &options);
+
+        assert!(matches!(result, Err(ParseError::LimitExceeded { line: 3 })));
+    }
+
+    #[test]
+    fn last_line_without_newline_fits_exact_byte_limit() {
+        let options = ParseOptions::builder().max_bytes(3).build();
+        let response = parse_with("a\nb", options);
+
+        assert_eq!(response.total_texts(), 2);
+    }
+
+    #[test]
+    fn crlf_counts_both_bytes_toward_limit() {
+        // Каждая строка вместе с `\r\n` занимает 3 байта, всего 9 байт.
+        let options = ParseOptions::builder().max_bytes(6).build();
+        let result = parse_str("a\r\nb\r\nc\r\n", &options);
+
+        assert!(matches!(result, Err(ParseError::LimitExceeded { line: 3 })));
+
+        let options = ParseOptions::builder().max_bytes(9).build();
+        assert_eq!(parse_with("a\r\nb\r\nc\r\n", options).total_texts(), 3);
+    }
+
+    #[test]
+    fn long_line_is_not_read_past_byte_limit() {
+        let input = format!("Eins -- 1\n{}", "x".repeat(100_000));
+        let mut read = 0;
+        let reader = BufReader::new(Counting {
+            inner: io::Cursor::new(input),
+            read: &mut read,
+        });
+        let options = ParseOptions::builder().max_bytes(20).build();
+
+        let result = parse_reader(reader, &options);
+
+        assert!(matches!(result, Err(ParseError::LimitExceeded { line: 2 })));
+        assert!(read <= 8 * 1024, "read {read} bytes");
+    }
+
+    #[test]
+    fn tab_separator_is_detected() {
+        let options = ParseOptions::builder().auto_detect_separator(true).build();
+        let response = parse_with("// Kopf\nEins\tОдин\nZwei\tДва\nDrei\tТри\n", options);
+
+        assert_eq!(response.separator, "\t");
+        assert_eq!(response.fields[0].content[2].translate, "Три");
+    }
+
+    #[test]
+    fn ambiguous_detection_falls_back_to_default() {
+        let options = ParseOptions::builder().auto_detect_separator(true).build();
+        let response = parse_with("a\tb = c\nd = e\tf\n", options);
+
+        assert_eq!(response.separator, DEFAULT_SEPARATOR);
+        assert_eq!(response.fields[0].content[0].original, "a\tb = c");
+    }
+
+    #[test]
+    fn separator_is_not_detected_by_default() {
+        let response = parse_text("Eins\tОдин\nZwei\tДва\n");
+
+        assert_eq!(response.separator, DEFAULT_SEPARATOR);
+    }
+
+    #[test]
+    fn escaped_hash_is_content() {
+        let response = parse_text("@sep =\n\\#foo = bar\n\\#winning -- x\n");
+        let originals: Vec<&str> = response
+            .iter_texts()
+            .map(|(_, text)| text.original.as_str())
+            .collect();
+
+        assert!(response.errors.is_empty());
+        assert!(response.all_tags.is_empty());
+        assert_eq!(originals, vec!["#foo", "#winning -- x"]);
+        assert_eq!(response.fields[0].content[0].translate, "bar");
+    }
+
+    #[test]
+    fn hash_without_escape_is_tag() {
+        let response = parse_text("@sep =\n#foo\nText = bar\n");
+
+        assert_eq!(tag_names(&response.fields[0]), vec!["foo"]);
+        assert_eq!(response.fields[0].content[0].original, "Text");
+    }
+
+    #[test]
+    fn json_contains_applied_separator() {
+        let detect = || ParseOptions::builder().auto_detect_separator(true).build();
+        let cases = [
+            (parse_text("Eins -- Один\n"), "--"),
+            (parse_text("@sep =>\nEins => Один\n"), "=>"),
+            (parse_with("Eins\tОдин\nZwei\tДва\n", detect()), "\t"),
+        ];
+
+        for (response, separator) in cases {
+            let json = serde_json::to_value(&*response).unwrap();
+
+            assert_eq!(json["separator"], separator);
+        }
+    }
+
+    /// Настройки, с которыми обрабатываются директивы `@include`.
+    fn with_include() -> ParseOptions {
+        return ParseOptions::builder().allow_include(true).build();
+    }
+
+    #[test]
+    fn include_inserts_fields_with_active_tags() {
+        let dir = temp_dir_with(&[
+            (
+                "main.txt",
+                "#screen\nEins -- Один\n@include parts/buttons.txt\nZwei -- Два\n",
+            ),
+            ("parts/buttons.txt", "#button\nOK -- Ладно\n"),
+        ]);
+
+        let response = parse(&dir.path().join("main.txt"), &with_include()).unwrap();
+
+        assert_eq!(response.fields.len(), 2);
+        assert_eq!(tag_names(&response.fields[0]), vec!["screen"]);
+        assert_eq!(response.fields[0].content.len(), 2);
+        assert_eq!(tag_names(&response.fields[1]), vec!["button", "screen"]);
+        assert_eq!(response.fields[1].content[0].original, "OK");
+    }
+
+    #[test]
+    fn cyclic_include_is_an_error() {
+        let dir = temp_dir_with(&[
+            ("a.txt", "Eins -- Один\n@include b.txt\n"),
+            ("b.txt", "Zwei -- Два\n@include a.txt\n"),
+        ]);
+
+        let result = parse(&dir.path().join("a.txt"), &with_include());
+
+        assert!(matches!(result, Err(ParseError::IncludeCycle(_))));
+    }
+
+    #[test]
+    fn include_is_skipped_when_disabled() {
+        let dir = temp_dir_with(&[
+            ("main.txt", "Eins -- Один\n@include part.txt\n"),
+            ("part.txt", "Zwei -- Два\n"),
+        ]);
+
+        let response = parse(&dir.path().join("main.txt"), &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.total_texts(), 1);
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::DisabledInclude);
+    }
+
+    #[test]
+    fn include_outside_directory_is_rejected() {
+        let dir = temp_dir_with(&[
+            ("main/main.txt", "@include ../secret.txt\n"),
+            ("secret.txt", "Geheim -- Секрет\n"),
+        ]);
+        let path = dir.path().join("main/main.txt");
+
+        let result = parse(&path, &with_include());
+
+        assert!(matches!(result, Err(ParseError::ForbiddenInclude(_))));
+
+        let absolute = dir.path().join("secret.txt");
+        fs::write(&path, format!("@include {}\n", absolute.display())).unwrap();
+
+        let result = parse(&path, &with_include());
+
+        assert!(matches!(result, Err(ParseError::ForbiddenInclude(_))));
+
+        let options = ParseOptions::builder()
+            .allow_include(true)
+            .allow_include_outside(true)
+            .build();
+        let response = parse(&path, &options).unwrap();
+
+        assert_eq!(response.fields[0].content[0].original, "Geheim");
+    }
+
+    #[test]
+    fn tag_aliases_collapse_into_one_field() {
+        let options = ParseOptions::builder()
+            .tag_alias("btn", "button")
+            .tag_alias("Button", "button")
+            .build();
+        let response = parse_with(
+            "#btn\nEins -- Один\n##button\n#Button\nZwei -- Два\n##btn\n#button\nDrei -- Три\n##Button\n#menu\nVier -- Четыре\n",
+            options,
+        );
+
+        assert_eq!(response.fields.len(), 2);
+        assert_eq!(tag_names(&response.fields[0]), vec!["button"]);
+        assert_eq!(response.fields[0].content.len(), 3);
+        assert_eq!(tag_names(&response.fields[1]), vec!["menu"]);
+    }
+
+    /// Файл, где один и тот же тэг записан в разном регистре.
+    const MIXED_CASE_TAGS: &str = "#Button\nEins -- Один\n##BUTTON\n#button\nZwei -- Два\n";
+
+    #[test]
+    fn case_insensitive_tags_merge() {
+        let options = ParseOptions::builder().case_insensitive_tags(true).build();
+        let response = parse_with(MIXED_CASE_TAGS, options);
+
+        assert_eq!(response.fields.len(), 1);
+        assert_eq!(tag_names(&response.fields[0]), vec!["button"]);
+        assert_eq!(response.fields[0].content.len(), 2);
+    }
+
+    #[test]
+    fn tags_are_case_sensitive_by_default() {
+        let response = parse_text(MIXED_CASE_TAGS);
+        let order: Vec<Vec<&str>> = response.fields.iter().map(tag_names).collect();
+
+        // `##BUTTON` не закрывает `#Button`, поэтому второй текст попадает в поле с обоими тэгами.
+        assert_eq!(order, vec![vec!["Button"], vec!["Button", "button"]]);
+    }
+
+    #[test]
+    fn texts_record_source_lines() {
+        let response = parse_text(
+            "// Kopf\n@lang de ru\n\n#a\nEins -- Один\n// Notiz\nZwei -- Два\n##a\nDrei -- Три\n",
+        );
+        let lines: Vec<u32> = response.iter_texts().map(|(_, text)| text.line).collect();
+
+        assert_eq!(lines, vec![5, 7, 9]);
+
+        let json = serde_json::to_value(&*response).unwrap();
+
+        assert_eq!(json["fields"][0]["content"][1]["line"], 7);
+    }
+
+    #[test]
+    fn zero_byte_file_gives_empty_response() {
+        let dir = temp_dir_with(&[("empty.txt", "")]);
+
+        let response = parse(&dir.path().join("empty.txt"), &ParseOptions::default()).unwrap();
+
+        assert!(response.is_empty());
+        assert!(response.errors.is_empty());
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn comments_only_file_gives_empty_response() {
+        let dir = temp_dir_with(&[("comments.txt", "// nur Kommentare\n\n@sep =\n// und @sep\n")]);
+
+        let response = parse(&dir.path().join("comments.txt"), &ParseOptions::default()).unwrap();
+
+        assert!(response.is_empty());
+        assert_eq!(response.separator, "=");
+        assert!(response.errors.is_empty());
+    }
+
+    /// Кодирует текст в Windows-1251.
+    #[cfg(feature = "encoding")]
+    fn cp1251(text: &str) -> Vec<u8> {
+        return encoding_rs::WINDOWS_1251.encode(text).0.into_owned();
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn cp1251_input_is_decoded_with_encoding_option() {
+        let bytes = cp1251("Привет -- Hallo\nПока -- Tschüss\n");
+        let options = ParseOptions::builder().encoding("windows-1251").build();
+
+        let response = parse_reader(bytes.as_slice(), &options).unwrap();
+
+        assert_eq!(response.fields[0].content[0].original, "Привет");
+        assert_eq!(response.fields[0].content[1].original, "Пока");
+        assert!(response.warnings.is_empty());
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn charset_directive_selects_encoding() {
+        let bytes = cp1251("@charset windows-1251\nПривет -- Hallo\n");
+
+        let response = parse_reader(bytes.as_slice(), &ParseOptions::default()).unwrap();
+
+        assert_eq!(response.fields[0].content[0].original, "Привет");
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn unknown_encoding_is_an_error() {
+        let options = ParseOptions::builder().encoding("klingon").build();
+
+        assert!(matches!(
+            parse_reader("a -- b\n".as_bytes(), &options),
+            Err(ParseError::UnknownEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn merge_joins_overlapping_and_keeps_disjoint_tag_sets() {
+        let mut left = parse_text("@lang de ru\n#a\nEins -- Один\n##a\n#b\nZwei -- Два\n");
+        let right =
+            parse_text("@lang de ru\n#a\nDrei -- Три\n##a\n#c\nVier <x> -- Четыре\nFünf -- Пять\n");
+
+        left.merge(*right);
+
+        assert_eq!(left.fields.len(), 3);
+        assert_eq!(tag_names(&left.fields[0]), vec!["a"]);
+        let originals: Vec<&str> = left.fields[0]
+            .content
+            .iter()
+            .map(|text| text.original.as_str())
+            .collect();
+        assert_eq!(originals, vec!["Eins", "Drei"]);
+        assert_eq!(tag_names(&left.fields[1]), vec!["b"]);
+        assert_eq!(tag_names(&left.fields[2]), vec!["c"]);
+        assert_eq!(left.errors.len(), 1);
+        assert!(left.warnings.is_empty());
+    }
+
+    #[test]
+    fn merge_warns_on_language_mismatch_and_keeps_own_languages() {
+        let mut left = parse_text("@lang de ru\nEins -- Один\n");
+        let right = parse_text("@lang en ru\nOne -- Один\n");
+
+        left.merge(*right);
+
+        assert_eq!(left.languages.original, "de");
+        assert_eq!(left.warnings.len(), 1);
+        assert_eq!(left.warnings[0].kind, WarningKind::LanguageMismatch);
+    }
+
+    #[test]
+    fn map_texts_uppercases_all_translations() {
+        let mut response = parse_text("#a\nEins -- один\n##a\n#b\nZwei -- два\n");
+
+        response.map_texts(|text| text.translate = text.translate.to_uppercase());
+
+        let translates: Vec<&str> = response
+            .fields
+            .iter()
+            .flat_map(|field| field.content.iter())
+            .map(|text| text.translate.as_str())
+            .collect();
+        assert_eq!(translates, vec!["ОДИН", "ДВА"]);
+    }
+
+    #[test]
+    fn trim_removes_surrounding_spaces_by_default() {
+        let response = parse_text("  and  --  und \n");
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(text.original, "and");
+        assert_eq!(text.translate, "und");
+    }
+
+    #[test]
+    fn trim_off_preserves_spaces_around_separator() {
+        let options = ParseOptions::builder().trim(false).build();
+        let response = parse_with("  and  --  und \r\n", options);
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.original, "  and  ");
+        assert_eq!(text.translate, "  und ");
+    }
+
+    #[test]
+    fn single_and_bulk_tags_nest_in_one_scope() {
+        let response =
+            parse_text("#a\n@tags b, c\nEins -- Один\n##a\nZwei -- Два\n@@tags b\nDrei -- Три\n");
+
+        assert_eq!(response.fields.len(), 3);
+        assert_eq!(tag_names(&response.fields[0]), vec!["a", "b", "c"]);
+        assert_eq!(response.fields[0].content[0].original, "Eins");
+        assert_eq!(tag_names(&response.fields[1]), vec!["b", "c"]);
+        assert_eq!(response.fields[1].content[0].original, "Zwei");
+        assert_eq!(tag_names(&response.fields[2]), vec!["c"]);
+        assert_eq!(response.fields[2].content[0].original, "Drei");
+    }
+
+    #[test]
+    fn validate_reports_clean_file_as_ok() {
+        let dir = temp_dir_with(&[("clean.txt", "#a\nEins -- Один\n")]);
+
+        let report = validate(&dir.path().join("clean.txt"), &ParseOptions::default()).unwrap();
+
+        assert!(report.is_ok);
+        assert!(report.errors().is_empty());
+    }
+
+    #[test]
+    fn validate_reports_forbidden_chars() {
+        let dir = temp_dir_with(&[("bad.txt", "Eins -- Один\nZwei <b> -- Два\n")]);
+
+        let report = validate(&dir.path().join("bad.txt"), &ParseOptions::default()).unwrap();
+
+        assert!(!report.is_ok);
+        assert_eq!(report.errors().len(), 1);
+        assert_eq!(report.errors()[0].location().line, 2);
+        assert_eq!(report.errors()[0].string(), "Zwei <b> -- Два");
+    }
+
+    #[test]
+    fn error_spans_count_chars_after_multibyte_text() {
+        let response = parse_text("Привет <мир> -- Hallo*\n");
+        let error = &response.errors[0];
+
+        assert_eq!(
+            error.spans(),
+            &[
+                Span {
+                    start_char: 7,
+                    end_char: 8
+                },
+                Span {
+                    start_char: 11,
+                    end_char: 12
+                },
+                Span {
+                    start_char: 21,
+                    end_char: 22
+                },
+            ]
+        );
+        assert_eq!(error.columns(), &[13, 20, 30]);
+        assert_eq!(error.location().column, Some(8));
+    }
+
+    #[test]
+    fn error_spans_skip_multibyte_separator() {
+        let response = parse_text("@sep →\nпривет → мир <b>\n");
+        let error = &response.errors[0];
+
+        assert_eq!(error.spans()[0].start_char, 13);
+        assert_eq!(error.spans()[0].end_char, 14);
+        assert_eq!(error.location().column, Some(14));
+    }
+
+    #[test]
+    fn error_spans_skip_quoted_multibyte_text() {
+        let response = parse_text("\"привет\" -- мир <b>\n");
+        let error = &response.errors[0];
+
+        assert_eq!(error.spans()[0].start_char, 16);
+        assert_eq!(error.spans()[0].end_char, 17);
+        assert_eq!(error.location().column, Some(17));
+    }
+
+    #[test]
+    fn default_separator_needs_no_env_file() {
+        assert!(!Path::new(env!("CARGO_MANIFEST_DIR")).join(".env").exists());
+
+        let response = parse_text("Eins -- Один\n");
+
+        assert_eq!(response.separator, "--");
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+    }
+
+    #[test]
+    fn separator_option_takes_precedence_over_default() {
+        let options = ParseOptions::builder().separator("=").build();
+        let response = parse_with("Eins = Один -- eins\n", options);
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.fields[0].content[0].translate, "Один -- eins");
+    }
+
+    #[test]
+    fn progress_is_reported_every_interval_lines() {
+        let text: String = (1..=10)
+            .map(|i| format!("Zeile {i} -- Строка {i}\n"))
+            .collect();
+        let dir = temp_dir_with(&[("lines.txt", &text)]);
+        let mut reported = Vec::new();
+
+        let response = parse_with_progress(
+            &dir.path().join("lines.txt"),
+            &ParseOptions::default(),
+            3,
+            |lines| reported.push(lines),
+        )
+        .unwrap();
+
+        assert_eq!(reported, vec![3, 6, 9]);
+        assert_eq!(response.fields[0].content.len(), 10);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_file_parses_like_plain_file() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let text = "@lang de ru\n#a\nEins -- Один\n##a\nZwei -- Два\n";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let dir = temp_dir_with(&[("plain.txt", text)]);
+        fs::write(dir.path().join("packed.txt.gz"), &compressed).unwrap();
+        fs::write(dir.path().join("packed.bin"), &compressed).unwrap();
+
+        let plain = parse(&dir.path().join("plain.txt"), &ParseOptions::default()).unwrap();
+        let expected = serde_json::to_value(&plain).unwrap();
+
+        for name in ["packed.txt.gz", "packed.bin"] {
+            let packed = parse(&dir.path().join(name), &ParseOptions::default()).unwrap();
+
+            assert_eq!(serde_json::to_value(&packed).unwrap(), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn quoted_original_keeps_separator_and_hash() {
+        let response = parse_text("@sep =\n\"#a = b\" = c\n");
+        let text = &response.fields[0].content[0];
+
+        assert!(response.fields[0].tags.is_empty());
+        assert_eq!(text.original, "#a = b");
+        assert_eq!(text.translate, "c");
+    }
+
+    #[test]
+    fn quoted_translate_keeps_forbidden_chars_and_doubled_quotes() {
+        let response = parse_text("@sep =\na = \"c # d <x> \"\"q\"\"\"\n");
+        let text = &response.fields[0].content[0];
+
+        assert!(response.errors.is_empty());
+        assert_eq!(text.original, "a");
+        assert_eq!(text.translate, "c # d <x> \"q\"");
+    }
+
+    #[test]
+    fn unterminated_quote_is_an_error() {
+        let response = parse_text("@sep =\n\"a = b\nc = d\n");
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].location().line, 2);
+        assert_eq!(response.fields[0].content.len(), 1);
+        assert_eq!(response.fields[0].content[0].original, "c");
+    }
+
+    #[test]
+    fn valid_languages_pass_validation() {
+        let options = ParseOptions::builder().validate_languages(true).build();
+        let response = parse_str("@lang de pt-BR\nEins -- Um\n", &options).unwrap();
+
+        assert_eq!(response.languages.translate, "pt-BR");
+    }
+
+    #[test]
+    fn unknown_language_is_rejected_when_validated() {
+        let options = ParseOptions::builder().validate_languages(true).build();
+
+        assert!(matches!(
+            parse_str("@lang ge ru\nEins -- Один\n", &options),
+            Err(ParseError::UnknownLanguage(code)) if code == "ge"
+        ));
+
+        let options = ParseOptions::builder()
+            .validate_languages(true)
+            .original_language("xx")
+            .build();
+
+        assert!(matches!(
+            parse_str("Eins -- Один\n", &options),
+            Err(ParseError::UnknownLanguage(code)) if code == "xx"
+        ));
+    }
+
+    #[test]
+    fn languages_are_not_validated_by_default() {
+        let response = parse_text("@lang ge ru\nEins -- Один\n");
+
+        assert_eq!(response.languages.original, "ge");
+    }
+
+    #[test]
+    fn trailing_comment_is_stripped() {
+        let options = ParseOptions::builder().forbidden_chars("").build();
+        let response = parse_with(
+            "// ganze Zeile\nHello -- Hallo  // greeting\nSite -- https:\\//example.org\nZitat -- \"a // b\"\n",
+            options,
+        );
+        let content = &response.fields[0].content;
+
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[0].translate, "Hallo");
+        assert_eq!(content[1].translate, "https://example.org");
+        assert_eq!(content[2].translate, "a // b");
+    }
+
+    #[test]
+    fn inline_comment_marker_is_configurable() {
+        let options = ParseOptions::builder().inline_comment(Some(";;")).build();
+        let response = parse_with("Hello -- Hallo ;; greeting\n", options);
+
+        assert_eq!(response.fields[0].content[0].translate, "Hallo");
+
+        let options = ParseOptions::builder().inline_comment(None).build();
+        let response = parse_with("Hello -- Hallo // greeting\n", options);
+
+        assert_eq!(response.errors.len(), 1);
+    }
+
+    #[test]
+    fn all_tags_collects_every_opened_tag_in_order() {
+        let response = parse_text(
+            "#zeta\nEins -- Один\n##zeta\n@tags beta, alpha\n@@tags beta\n#gamma\n##gamma\nZwei -- Два\n",
+        );
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(
+            json["all_tags"],
+            serde_json::json!(["alpha", "beta", "gamma", "zeta"])
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_dir_parse_matches_serial() {
+        let files: Vec<(String, String)> = (0..40)
+            .map(|i| {
+                let text = format!(
+                    "#t{}\nZeile {i} -- Строка {i}\nBöse {i} <b> -- Плохо\n",
+                    i % 3
+                );
+                return (format!("f{i:02}.txt"), text);
+            })
+            .collect();
+        let files: Vec<(&str, &str)> = files
+            .iter()
+            .map(|(name, text)| (name.as_str(), text.as_str()))
+            .collect();
+        let dir = temp_dir_with(&files);
+
+        let serial = parse_dir(dir.path(), false, &ParseOptions::default()).unwrap();
+        let parallel = parse_dir_parallel(dir.path(), false, &ParseOptions::default()).unwrap();
+
+        assert_eq!(serial.errors.len(), 40);
+        assert_eq!(
+            serde_json::to_value(&parallel).unwrap(),
+            serde_json::to_value(&serial).unwrap()
+        );
+    }
+
+    #[test]
+    fn multi_char_separator_is_trimmed_when_unquoted() {
+        let response = parse_text("@sep ->  \nEins -> Один\n");
+
+        assert_eq!(response.separator, "->");
+        assert_eq!(response.fields[0].content[0].original, "Eins");
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+    }
+
+    #[test]
+    fn quoted_separator_keeps_spaces() {
+        let response = parse_text("@sep \" - \"\nEins-zwei - Один\n");
+        let text = &response.fields[0].content[0];
+
+        assert_eq!(response.separator, " - ");
+        assert_eq!(text.original, "Eins-zwei");
+        assert_eq!(text.translate, "Один");
+
+        let response = parse_text("@sep \" | \"\nEins | Один\n");
+
+        assert_eq!(response.separator, " | ");
+        assert!(response.errors.is_empty());
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+    }
+
+    #[test]
+    fn bare_close_pops_most_recent_tag() {
+        let response = parse_text(
+            "#a\n#b\nEins -- Один\n##\nZwei -- Два\n##\nDrei -- Три\n##\nVier -- Четыре\n",
+        );
+
+        assert_eq!(response.fields.len(), 3);
+        assert_eq!(tag_names(&response.fields[0]), vec!["a", "b"]);
+        assert_eq!(tag_names(&response.fields[1]), vec!["a"]);
+        assert!(response.fields[2].tags.is_empty());
+        assert_eq!(response.fields[2].content.len(), 2);
+    }
+
+    #[test]
+    fn custom_tag_prefixes_open_and_close_tags() {
+        let options = ParseOptions::builder()
+            .tag_prefix("$")
+            .bulk_tag_prefix("%scopes")
+            .build();
+        let response = parse_with(
+            "$scope\n%scopes x, y\nEins -- Один\n$$scope\n%%scopes x\nZwei -- Два\n#kein -- Tag\n",
+            options,
+        );
+
+        assert_eq!(response.fields.len(), 2);
+        assert_eq!(tag_names(&response.fields[0]), vec!["scope", "x", "y"]);
+        assert_eq!(tag_names(&response.fields[1]), vec!["y"]);
+        assert_eq!(response.fields[1].content[1].original, "#kein");
+    }
+
+    /// Источник, который считает прочитанные из него байты.
+    struct Counting<'a, R> {
+        inner: R,
+        read: &'a mut usize,
+    }
+
+    impl<R: io::Read> io::Read for Counting<'_, R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            *self.read += read;
+
+            return Ok(read);
+        }
+    }
+
+    #[test]
+    fn input_is_read_once_and_lines_keep_numbers() {
+        let input = "// Kopf\n\n@sep =\n#a\nEins = Один\nZwei <b> = Два\n";
+        let mut read = 0;
+        let reader = BufReader::new(Counting {
+            inner: io::Cursor::new(input),
+            read: &mut read,
+        });
+
+        let response = parse_reader(reader, &ParseOptions::default()).unwrap();
+
+        assert_eq!(read, input.len());
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.fields[0].content[0].line, 5);
+        assert_eq!(response.errors[0].location().line, 6);
+    }
+
+    /// Возвращает отсортированные ключи объекта JSON.
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+
+        return keys;
+    }
+
+    #[test]
+    fn json_keys_match_documented_schema() {
+        let options = ParseOptions::builder()
+            .alternatives_separator(";")
+            .track_sep_offset(true)
+            .keep_raw(true)
+            .keep_header_comments(true)
+            .collect_directives(true)
+            .warn_unclosed_tags(true)
+            .max_errors(1)
+            .build();
+        let response = parse_with(
+            "// Kopf\n@lang de ru\n#a\n@comment Notiz\nEins -- Один; Раз\nZwei <b> -- Два\nDrei <c> -- Три\n@lang en ru\nOne -- Один\n",
+            options,
+        );
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(
+            keys(&json),
+            vec![
+                "all_tags",
+                "directives",
+                "errors",
+                "errors_truncated",
+                "fields",
+                "header_comments",
+                "languages",
+                "separator",
+                "warnings",
+            ]
+        );
+        assert_eq!(keys(&json["languages"]), vec!["original", "translate"]);
+        assert_eq!(
+            keys(&json["fields"][0]),
+            vec!["comments", "content", "tags"]
+        );
+        assert_eq!(
+            keys(&json["fields"][1]),
+            vec!["content", "languages", "tags"]
+        );
+        assert_eq!(
+            keys(&json["fields"][0]["content"][0]),
+            vec![
+                "line",
+                "original",
+                "raw",
+                "sep_offset",
+                "translate",
+                "translates"
+            ]
+        );
+        assert_eq!(
+            keys(&json["errors"][0]),
+            vec!["column", "columns", "line", "spans", "string"]
+        );
+        assert_eq!(
+            keys(&json["errors"][0]["spans"][0]),
+            vec!["end_char", "start_char"]
+        );
+        assert_eq!(keys(&json["warnings"][0]), vec!["kind", "line", "message"]);
+        assert_eq!(keys(&json["directives"][0]), vec!["kind", "line", "raw"]);
+    }
+
+    #[test]
+    fn json_omits_empty_optional_keys() {
+        let json = serde_json::to_value(parse_text("Eins -- Один\n")).unwrap();
+
+        assert_eq!(
+            keys(&json),
+            vec!["all_tags", "errors", "fields", "languages", "separator"]
+        );
+        assert_eq!(keys(&json["fields"][0]), vec!["content", "tags"]);
+        assert_eq!(
+            keys(&json["fields"][0]["content"][0]),
+            vec!["line", "original", "translate"]
+        );
+    }
+
+    #[test]
+    fn exceeding_max_active_tags_skips_directive_with_warning() {
+        let options = ParseOptions::builder().max_active_tags(2).build();
+        let response = parse_with("#a\n#b\n#c\nEins -- Один\n", options);
+
+        assert_eq!(tag_names(&response.fields[0]), vec!["a", "b"]);
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::TooManyTags);
+        assert_eq!(response.warnings[0].location.line, 3);
+    }
+
+    #[test]
+    fn exceeding_max_active_tags_can_fail() {
+        let options = ParseOptions::builder()
+            .max_active_tags(2)
+            .fail_on_too_many_tags(true)
+            .build();
+
+        assert!(matches!(
+            parse_str("#a\n@tags b, c\nEins -- Один\n", &options),
+            Err(ParseError::TooManyTags { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn active_tags_are_unlimited_by_default() {
+        let input: String = (0..100).map(|i| format!("#t{i}\n")).collect();
+        let response = parse_text(&format!("{input}Eins -- Один\n"));
+
+        assert_eq!(response.fields[0].tags.len(), 100);
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn counts_fields_and_texts() {
+        let response = parse_text(
+            "Null -- Ноль\n#a\nEins -- Один\nZwei -- Два\n##a\n#b\nDrei -- Три\nBöse <x> -- Плохо\n",
+        );
+
+        assert_eq!(response.field_count(), 3);
+        assert_eq!(response.total_texts(), 4);
+        let lens: Vec<usize> = response.fields().iter().map(Field::content_len).collect();
+        assert_eq!(lens, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn unclosed_tag_is_reported_with_opening_line() {
+        let options = ParseOptions::builder().warn_unclosed_tags(true).build();
+        let response = parse_with("#b\n##b\n\n#a\nEins -- Один\n", options);
+
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::UnclosedTag);
+        assert_eq!(response.warnings[0].location.line, 4);
+        assert!(response.warnings[0].message.contains("`a`"));
+    }
+
+    #[test]
+    fn closed_tags_are_not_reported() {
+        let options = ParseOptions::builder().warn_unclosed_tags(true).build();
+        let response = parse_with("#a\n@tags b, c\nEins -- Один\n@@tags b, c\n##a\n", options);
+
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn tags_list_continues_after_trailing_comma() {
+        let response =
+            parse_text("@tags a, b,\n  c,\n d\nEins -- Один\n@@tags a,\nb\nZwei -- Два\n");
+
+        assert_eq!(tag_names(&response.fields[0]), vec!["a", "b", "c", "d"]);
+        assert_eq!(response.fields[0].content[0].line, 4);
+        assert_eq!(tag_names(&response.fields[1]), vec!["c", "d"]);
+        assert!(response.errors.is_empty());
+    }
+
+    #[test]
+    fn tags_list_ending_with_comma_at_eof_is_closed() {
+        let response = parse_text("Eins -- Один\n@tags a,\n");
+
+        assert_eq!(response.fields.len(), 1);
+        assert_eq!(response.all_tags.len(), 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tracing_test::traced_test]
+    #[test]
+    fn error_line_emits_tracing_event() {
+        let response = parse_text("Eins -- Один\nZwei <b> -- Два\n");
+
+        assert_eq!(response.errors.len(), 1);
+        assert!(logs_contain("запрещенные символы в строке"));
+        assert!(logs_contain("line=2"));
+    }
+
+    #[test]
+    fn comment_directives_attach_to_their_field() {
+        let response = parse_text(
+            "#a\n@comment Kontext eins\nEins -- Один\n@comment noch eins\n##a\n#b\n@comment für b\nZwei -- Два\n",
+        );
+
+        assert_eq!(
+            response.fields[0].comments,
+            vec!["Kontext eins", "noch eins"]
+        );
+        assert_eq!(response.fields[1].comments, vec!["für b"]);
+        assert_eq!(response.total_texts(), 2);
+    }
+
+    #[test]
+    fn slash_comments_are_not_kept() {
+        let response = parse_text("#a\n// verworfen\nEins -- Один\n");
+
+        assert!(response.fields[0].comments.is_empty());
+    }
+
+    /// Текст с запрещенными символами в строках 3 и 10.
+    const ERRORS_ON_3_AND_10: &str =
+        "Eins -- Один\nZwei -- Два\nDrei <3> -- Три\n4 -- 4\n5 -- 5\n6 -- 6\n7 -- 7\n8 -- 8\n9 -- 9\nZehn * -- Десять\n";
+
+    #[test]
+    fn fail_fast_stops_on_first_error() {
+        let options = ParseOptions::builder().fail_fast(true).build();
+
+        match parse_str(ERRORS_ON_3_AND_10, &options) {
+            Err(ParseError::InvalidContent { line, string }) => {
+                assert_eq!(line, 3);
+                assert_eq!(string, "Drei <3> -- Три");
+            }
+            _ => panic!("ожидалась ошибка InvalidContent"),
+        }
+    }
+
+    #[test]
+    fn errors_are_collected_without_fail_fast() {
+        let response = parse_text(ERRORS_ON_3_AND_10);
+        let lines: Vec<u32> = response
+            .errors
+            .iter()
+            .map(|error| error.location.line)
+            .collect();
+
+        assert_eq!(lines, vec![3, 10]);
+        assert_eq!(response.total_texts(), 8);
+    }
+
+    #[test]
+    fn raw_line_is_kept_verbatim() {
+        let options = ParseOptions::builder().keep_raw(true).build();
+        let response = parse_with("#a\n  Eins  --\tОдин \\-- x  \r\nZwei -- Два", options);
+        let content = &response.fields[0].content;
+
+        assert_eq!(content[0].raw.as_deref(), Some("  Eins  --\tОдин \\-- x  "));
+        assert_eq!(content[1].raw.as_deref(), Some("Zwei -- Два"));
+    }
+
+    #[test]
+    fn raw_line_is_not_kept_by_default() {
+        let response = parse_text("Eins -- Один\n");
+
+        assert_eq!(response.fields[0].content[0].raw, None);
+    }
+
+    #[test]
+    fn mid_file_lang_changes_later_fields_only() {
+        let response = parse_text(
+            "@lang de ru\n#a\nEins -- Один\n##a\n@lang en fr\n#a\nOne -- Un\n##a\n@lang de ru\nZwei -- Два\n",
+        );
+
+        assert_eq!(response.languages, Languages::new("de", "ru"));
+        assert_eq!(response.fields.len(), 3);
+        assert_eq!(response.fields[0].languages, None);
+        assert_eq!(
+            response.fields[1].languages,
+            Some(Languages::new("en", "fr"))
+        );
+        assert_eq!(response.fields[1].content[0].original, "One");
+        assert_eq!(response.fields[2].languages, None);
+    }
+
+    #[test]
+    fn allowed_chars_follow_block_languages() {
+        let options = ParseOptions::builder()
+            .allowed_chars("ru", cyrillic())
+            .build();
+        let response = parse_with(
+            "@lang de ru\nEins -- Один\n@lang ru de\nПривет -- Hallo\nПривеa -- Hallo\n",
+            options,
+        );
+
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::DisallowedCharacters);
+        assert_eq!(response.warnings[0].location.line, 5);
+    }
+
+    #[test]
+    fn separator_equal_to_tag_or_comment_marker_is_rejected() {
+        for input in [
+            "@sep #\na # b\n",
+            "@sep //\na // b\n",
+            "@sep @tags\na @tags b\n",
+        ] {
+            assert!(
+                matches!(
+                    parse_str(input, &ParseOptions::default()),
+                    Err(ParseError::InvalidSeparator(_))
+                ),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn separator_directive_with_trailing_comment_is_accepted() {
+        let response = parse_text("@sep = // Gleichheitszeichen\nEins = Один\n");
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+    }
+
+    #[test]
+    fn empty_response_distinguishes_clean_and_failed_input() {
+        let clean = parse_text("// nur Kommentar\n\n");
+
+        assert!(clean.is_empty());
+        assert!(clean.errors.is_empty());
+
+        let failed = parse_text("Eins <b> -- Один\nZwei * -- Два\n");
+
+        assert!(failed.is_empty());
+        assert_eq!(failed.errors.len(), 2);
+
+        assert!(!parse_text("Eins -- Один\n").is_empty());
+    }
+
+    #[test]
+    fn line_at_max_len_is_kept_and_longer_one_skipped() {
+        let options = ParseOptions::builder().max_line_len(20).build();
+        let at_limit = format!("Eins -- {}", "a".repeat(12));
+        let over_limit = format!("Zwei -- {}", "я".repeat(7));
+        let response = parse_with(&format!("{at_limit}\n{over_limit}\nDrei -- Три\n"), options);
+
+        assert_eq!(response.total_texts(), 2);
+        assert_eq!(response.fields[0].content[0].translate, "a".repeat(12));
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::LineTooLong);
+        assert_eq!(response.warnings[0].location.line, 2);
+        assert!(response.warnings[0].message.contains("22"));
+    }
+
+    #[test]
+    fn max_line_len_ignores_crlf() {
+        let options = ParseOptions::builder().max_line_len(10).build();
+        let at_limit = format!("Eins -- {}", "a".repeat(2));
+        let over_limit = format!("Zwei -- {}", "b".repeat(3));
+        let input = format!("{at_limit}\r\n{over_limit}\r\nDrei -- 3\r\n");
+        let response = parse_with(&input, options);
+
+        assert_eq!(response.total_texts(), 2);
+        assert_eq!(response.fields[0].content[0].translate, "aa");
+        assert_eq!(response.fields[0].content[1].line, 3);
+        assert_eq!(response.warnings[0].location.line, 2);
+        assert!(response.warnings[0].message.contains("11"));
+    }
+
+    #[test]
+    fn long_line_is_skipped_without_buffering() {
+        let input = format!("Eins -- 1\n{}\nZwei -- 2", "x".repeat(1_000_000));
+        let options = ParseOptions::builder().max_line_len(20).build();
+        let mut lines = LineReader::new(io::Cursor::new(input.as_bytes()), &options).unwrap();
+
+        lines.next();
+        let mut buf = Vec::new();
+        let read = lines.read_raw_line(&mut buf).unwrap();
+
+        assert_eq!(read, 1_000_001);
+        assert!(buf.capacity() < 1024);
+        assert_eq!(lines.too_long, Some(1_000_000));
+
+        let response = parse_with(&input, ParseOptions::builder().max_line_len(20).build());
+        assert_eq!(response.total_texts(), 2);
+        assert!(response.warnings[0].message.contains("1000000"));
+    }
+
+    #[test]
+    fn tag_serializes_as_plain_string() {
+        let tag = Tag::new("  menu ").unwrap();
+
+        assert_eq!(tag.as_str(), "menu");
+        assert_eq!(serde_json::to_string(&tag).unwrap(), "\"menu\"");
+        assert_eq!(serde_json::from_str::<Tag>("\"menu\"").unwrap(), tag);
+        assert_eq!(Tag::new("   "), None);
+    }
+
+    #[test]
+    fn field_tags_serialize_as_sorted_strings() {
+        let response = parse_text("@tags b, a\nEins -- Один\n");
+        let json = serde_json::to_value(&response.fields[0]).unwrap();
+
+        assert_eq!(json["tags"], serde_json::json!(["a", "b"]));
+    }
+
+    #[test]
+    fn sort_content_orders_texts_case_insensitively() {
+        let options = ParseOptions::builder().sort_content(true).build();
+        let response = parse_with(
+            "#a\nzwei -- 2\nEins -- 1\nDrei -- 3\ndrei -- 3b\n##a\nB -- b\nA -- a\n",
+            options,
+        );
+        let originals = |field: &Field| -> Vec<String> {
+            return field.content.iter().map(|x| x.original.clone()).collect();
+        };
+
+        assert_eq!(
+            originals(&response.fields[0]),
+            vec!["Drei", "drei", "Eins", "zwei"]
+        );
+        assert_eq!(originals(&response.fields[1]), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn content_keeps_file_order_by_default() {
+        let response = parse_text("zwei -- 2\nEins -- 1\n");
+
+        assert_eq!(response.fields[0].content[0].original, "zwei");
+    }
+
+    #[test]
+    fn directives_are_collected_with_kind_and_line() {
+        let options = ParseOptions::builder().collect_directives(true).build();
+        let response = parse_with(
+            "// Kopf\n@sep =\n@lang de ru\n@tags a, b\n@comment Notiz\nEins = Один\n@@tags a, b\n",
+            options,
+        );
+        let found: Vec<(DirectiveKind, u32, &str)> = response
+            .directives
+            .iter()
+            .map(|x| (x.kind, x.location.line, x.raw.as_str()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (DirectiveKind::Separator, 2, "@sep ="),
+                (DirectiveKind::Language, 3, "@lang de ru"),
+                (DirectiveKind::Tags, 4, "@tags a, b"),
+                (DirectiveKind::Comment, 5, "@comment Notiz"),
+                (DirectiveKind::Tags, 7, "@@tags a, b"),
+            ]
+        );
+        assert_eq!(response.fields[0].content[0].translate, "Один");
+    }
+
+    #[test]
+    fn directives_are_not_collected_by_default() {
+        let response = parse_text("@sep =\n@lang de ru\nEins = Один\n");
+
+        assert!(response.directives.is_empty());
+    }
+
+    #[test]
+    fn misspelled_directive_fails_in_strict_mode() {
+        let options = ParseOptions::builder().strict_directives(true).build();
+
+        match parse_str("Eins -- Один\n@seperator =\n", &options) {
+            Err(ParseError::UnknownDirective { line, directive }) => {
+                assert_eq!(line, 2);
+                assert_eq!(directive, "@seperator");
+            }
+            _ => panic!("ожидалась ошибка UnknownDirective"),
+        }
+
+        let response = parse_str("@sep =\n@lang de ru\nEins = Один\n", &options).unwrap();
+
+        assert_eq!(response.separator, "=");
+    }
+
+    #[test]
+    fn directive_without_value_is_invalid_in_strict_mode() {
+        let options = ParseOptions::builder().strict_directives(true).build();
+
+        for input in ["Eins -- Один\n@comment\n", "Eins -- Один\n@include   \n"] {
+            match parse_str(input, &options) {
+                Err(ParseError::InvalidDirective { line, directive }) => {
+                    assert_eq!(line, 2);
+                    assert!(["@comment", "@include"].contains(&directive.as_str()));
+                }
+                _ => panic!("ожидалась ошибка InvalidDirective для {input:?}"),
+            }
+        }
+
+        assert!(matches!(
+            parse_str("@commentary\n", &options),
+            Err(ParseError::UnknownDirective { .. })
+        ));
+    }
+
+    #[test]
+    fn misspelled_directive_is_text_by_default() {
+        let response = parse_text("@sep =\n@seperator = x\nEins = Один\n");
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.fields[0].content[0].original, "@seperator");
+        assert_eq!(response.fields[0].content[0].translate, "x");
+        assert_eq!(response.total_texts(), 2);
+    }
+
+    /// Тэги поля, оригинал и перевод текста.
+    type TaggedText<'a> = (Vec<&'a str>, &'a str, &'a str);
+
+    /// Тексты с тэгами и строки с ошибками без номеров строк, чтобы сравнивать
+    /// результаты для файлов с разным началом.
+    fn texts_and_errors(response: &Response) -> (Vec<TaggedText<'_>>, Vec<&str>) {
+        let texts = response
+            .fields
+            .iter()
+            .flat_map(|field| {
+                let tags = tag_names(field);
+                return field.content.iter().map(move |text| {
+                    (
+                        tags.clone(),
+                        text.original.as_str(),
+                        text.translate.as_str(),
+                    )
+                });
+            })
+            .collect();
+        let errors = response.errors.iter().map(|x| x.string.as_str()).collect();
+
+        return (texts, errors);
+    }
+
+    #[test]
+    fn fast_and_slow_start_give_same_result() {
+        let body: String = (0..1500)
+            .map(|i| match i % 100 {
+                0 => format!("#t{}\n", i / 100),
+                99 => format!("##t{}\n", i / 100),
+                50 => format!("Böse {i} <b> = Плохо\n"),
+                _ => format!("Zeile {i} = Строка {i}\n"),
+            })
+            .collect();
+
+        let fast = parse_with(&body, ParseOptions::builder().separator("=").build());
+        let declared = parse_text(&format!("// Kopf\n\n@sep =\n{body}"));
+        let detected = parse_text(&format!("@sep auto\n{body}"));
+
+        assert_eq!(fast.separator, "=");
+        assert_eq!(fast.total_texts(), 1455);
+        assert_eq!(declared.separator, "=");
+        assert_eq!(detected.separator, "=");
+        assert_eq!(texts_and_errors(&declared), texts_and_errors(&fast));
+        assert_eq!(texts_and_errors(&detected), texts_and_errors(&fast));
+    }
+
+    #[test]
+    fn raw_block_disables_separator_splitting() {
+        let response =
+            parse_text("@sep :\nkey: value\n@raw\napp.title: Titel\napp.ok: OK\n@@raw\nJa: Да\n");
+        let pairs: Vec<(&str, &str)> = response.fields[0]
+            .content
+            .iter()
+            .map(|x| (x.original.as_str(), x.translate.as_str()))
+            .collect();
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("key", "value"),
+                ("app.title: Titel", ""),
+                ("app.ok: OK", ""),
+                ("Ja", "Да"),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_tag_blocks_are_deduplicated() {
+        let options = ParseOptions::builder().dedup_content(true).build();
+        let response = parse_with(
+            "#a\nEins -- Один\nZwei -- Два\n##a\n\n#a\nZwei -- Два\nDrei -- Три\nEins -- Один\nEins -- Раз\n##a\n",
+            options,
+        );
+        let pairs: Vec<(&str, &str)> = response.fields[0]
+            .content
+            .iter()
+            .map(|x| (x.original.as_str(), x.translate.as_str()))
+            .collect();
+
+        assert_eq!(response.fields.len(), 1);
+        assert_eq!(
+            pairs,
+            vec![
+                ("Eins", "Один"),
+                ("Zwei", "Два"),
+                ("Drei", "Три"),
+                ("Eins", "Раз"),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_texts_are_kept_by_default() {
+        let response = parse_text("#a\nEins -- Один\n##a\n#a\nEins -- Один\n##a\n");
+
+        assert_eq!(response.fields[0].content.len(), 2);
+    }
+
+    #[test]
+    fn fields_keep_content_and_tags_when_blocks_repeat() {
+        let response = parse_text(
+            "Null -- Ноль\n#a\nEins -- Один\n#b\nZwei -- Два\n##b\nDrei -- Три\n##a\n#b\nVier -- Четыре\n##b\n#a\nFünf -- Пять\n#b\nSechs -- Шесть\n",
+        );
+
+        assert_eq!(
+            serde_json::to_value(&response.fields).unwrap(),
+            serde_json::json!([
+                {
+                    "tags": [],
+                    "content": [{ "original": "Null", "translate": "Ноль", "line": 1 }],
+                },
+                {
+                    "tags": ["a"],
+                    "content": [
+                        { "original": "Eins", "translate": "Один", "line": 3 },
+                        { "original": "Drei", "translate": "Три", "line": 7 },
+                        { "original": "Fünf", "translate": "Пять", "line": 13 },
+                    ],
+                },
+                {
+                    "tags": ["a", "b"],
+                    "content": [
+                        { "original": "Zwei", "translate": "Два", "line": 5 },
+                        { "original": "Sechs", "translate": "Шесть", "line": 15 },
+                    ],
+                },
+                {
+                    "tags": ["b"],
+                    "content": [{ "original": "Vier", "translate": "Четыре", "line": 10 }],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn sep_auto_directive_detects_equals() {
+        let response = parse_text("// Kopf\n@sep auto\nEins = Один\nZwei = Два\nDrei = Три\n");
+
+        assert_eq!(response.separator, "=");
+        assert_eq!(response.total_texts(), 3);
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn sep_auto_without_clear_separator_warns() {
+        let response = parse_text("@sep auto\nEins Один\nZwei Два\n");
+
+        assert_eq!(response.separator, DEFAULT_SEPARATOR);
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(response.warnings[0].kind, WarningKind::AmbiguousSeparator);
+    }
+
+    #[test]
+    fn sep_auto_after_scan_limit_warns() {
+        let comments = "//\n".repeat(SEPARATOR_SCAN_LINES);
+        let response = parse_text(&format!("{comments}@sep auto\nEins = Один\n"));
+
+        assert_eq!(response.separator, DEFAULT_SEPARATOR);
+        assert_eq!(response.warnings.len(), 1);
+        assert_eq!(
+            response.warnings[0].location.line as usize,
+            SEPARATOR_SCAN_LINES + 1
+        );
+    }
+
+    #[test]
+    fn error_and_warning_share_location_format() {
+        let options = ParseOptions::builder()
+            .allowed_chars("ru", cyrillic())
+            .collect_directives(true)
+            .build();
+        let response = parse_with("@lang de ru\n  Zwei <b> -- Два\nDrei -- Триx\n", options);
+
+        assert_eq!(
+            response.errors[0].location(),
+            Location::new(2).with_column(8)
+        );
+        assert_eq!(response.warnings[0].location().line, 3);
+        assert_eq!(response.directives[0].location, Location::new(1));
+
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(json["errors"][0]["line"], 2);
+        assert_eq!(json["errors"][0]["column"], 8);
+        assert_eq!(json["warnings"][0]["line"], 3);
+        assert_eq!(json["directives"][0]["line"], 1);
+    }
+
+    /// Оригиналы и переводы всех текстов.
+    fn pairs(response: &Response) -> Vec<(&str, &str)> {
+        return response
+            .fields
+            .iter()
+            .flat_map(|field| field.content.iter())
+            .map(|text| (text.original.as_str(), text.translate.as_str()))
+            .collect();
+    }
+
+    #[test]
+    fn borrowed_and_changed_texts_are_unchanged() {
+        let input = "Eins -- Один\n  Zwei  --  Два  \nDrei \\-- x -- Три \\-- y\n\\#vier -- Четыре\n\"a -- b\" -- \"c \"\"d\"\"\"\nFünf --\n";
+
+        assert_eq!(
+            pairs(&parse_text(input)),
+            vec![
+                ("Eins", "Один"),
+                ("Zwei", "Два"),
+                ("Drei -- x", "Три -- y"),
+                ("#vier", "Четыре"),
+                ("a -- b", "c \"d\""),
+                ("Fünf", ""),
+            ]
+        );
+
+        let options = ParseOptions::builder().trim(false).build();
+
+        assert_eq!(
+            pairs(&parse_with(input, options)),
+            vec![
+                ("Eins ", " Один"),
+                ("  Zwei  ", "  Два  "),
+                ("Drei -- x ", " Три -- y"),
+                ("#vier ", " Четыре"),
+                ("a -- b", "c \"d\""),
+                ("Fünf ", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn bcp47_codes_with_region_and_script_are_accepted() {
+        let options = ParseOptions::builder().validate_languages(true).build();
+
+        for (input, translate) in [
+            ("@lang de pt-BR\n", "pt-BR"),
+            ("@lang de zh-Hans\n", "zh-Hans"),
+            ("@lang de sr-Latn-RS\n", "sr-Latn-RS"),
+        ] {
+            let response = parse_str(input, &options).unwrap();
+
+            assert_eq!(response.languages.translate, translate);
+        }
+    }
+
+    #[test]
+    fn malformed_bcp47_code_is_rejected() {
+        let options = ParseOptions::builder().validate_languages(true).build();
+
+        for code in ["en--US", "en-", "pt-BRAZILIAN-x-"] {
+            assert!(
+                matches!(
+                    parse_str(&format!("@lang {code} ru\n"), &options),
+                    Err(ParseError::UnknownLanguage(x)) if x == code
+                ),
+                "{code}"
+            );
+        }
+    }
+
+    #[test]
+    fn error_line_is_also_parsed_with_errors_as_warnings() {
+        let options = ParseOptions::builder().errors_as_warnings(true).build();
+        let response = parse_with("#a\nEins -- Один\nZwei <b> -- Два\n", options);
+
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].location.line, 3);
+        assert_eq!(
+            pairs(&response),
+            vec![("Eins", "Один"), ("Zwei <b>", "Два")]
+        );
+        assert_eq!(tag_names(&response.fields[0]), vec!["a"]);
+    }
+
+    #[test]
+    fn error_line_is_not_parsed_by_default() {
+        let response = parse_text("Eins -- Один\nZwei <b> -- Два\n");
+
+        assert_eq!(pairs(&response), vec![("Eins", "Один")]);
+    }
+
+    #[test]
+    fn iter_texts_yields_every_text_with_field_tags() {
+        let response = parse_text("Null -- Ноль\n#a\nEins -- Один\nZwei -- Два\n#b\nDrei -- Три\n");
+        let mut collected = Vec::new();
+
+        for (tags, text) in &*response {
+            let mut tags: Vec<&str> = tags.iter().map(Tag::as_str).collect();
+            tags.sort();
+            collected.push((tags, text.original.as_str()));
+        }
+
+        assert_eq!(
+            collected,
+            vec![
+                (vec![], "Null"),
+                (vec!["a"], "Eins"),
+                (vec!["a"], "Zwei"),
+                (vec!["a", "b"], "Drei"),
+            ]
+        );
+        assert_eq!(response.iter_texts().count(), response.total_texts());
+    }
+
+    #[test]
+    fn errors_are_truncated_at_max_errors() {
+        let options = ParseOptions::builder().max_errors(2).build();
+        let input: String = (1..=5)
+            .map(|i| format!("Zeile {i} <b> -- Строка\n"))
+            .collect();
+        let response = parse_with(&format!("{input}Eins -- Один\n"), options);
+        let lines: Vec<u32> = response.errors.iter().map(|x| x.location.line).collect();
+
+        assert_eq!(lines, vec![1, 2]);
+        assert!(response.errors_truncated);
+        assert_eq!(response.total_texts(), 1);
+        assert_eq!(
+            serde_json::to_value(&response).unwrap()["errors_truncated"],
+            true
+        );
+    }
+
+    #[test]
+    fn errors_at_max_errors_are_not_truncated() {
+        let options = ParseOptions::builder().max_errors(2).build();
+        let response = parse_with("a <b> -- c\nd <e> -- f\n", options);
+
+        assert_eq!(response.errors.len(), 2);
+        assert!(!response.errors_truncated);
+    }
+
+    #[test]
+    fn forced_languages_win_over_directive_and_options() {
+        let options = ParseOptions::builder()
+            .original_language("en")
+            .force_languages(Languages::new("c", "d"))
+            .build();
+        let response = parse_with("@lang a b\nEins -- Один\n@lang x y\nZwei -- Два\n", options);
+
+        assert_eq!(response.languages, Languages::new("c", "d"));
+        assert!(response
+            .fields
+            .iter()
+            .all(|field| field.languages.is_none()));
+    }
+
+    #[test]
+    fn directive_wins_over_option_languages() {
+        let options = ParseOptions::builder().original_language("en").build();
+        let response = parse_with("@lang a b\nEins -- Один\n", options);
+
+        assert_eq!(response.languages, Languages::new("a", "b"));
+    }
+
+    #[test]
+    fn header_banner_is_kept_verbatim() {
+        let options = ParseOptions::builder().keep_header_comments(true).build();
+        let response = parse_with(
+            "// Copyright (c) Acme\n//   Lizenz: MIT\n//\n\n@sep =\n// nach @sep\nEins = Один\n// später\n",
+            options,
+        );
+
+        assert_eq!(
+            response.header_comments,
+            vec![" Copyright (c) Acme", "   Lizenz: MIT", "", " nach @sep"]
+        );
+    }
+
+    #[test]
+    fn header_comments_are_dropped_by_default() {
+        let response = parse_text("// Kopf\nEins -- Один\n");
+
+        assert!(response.header_comments.is_empty());
+    }
+
+    /// Источник, который отдает части по очереди, а вместо `None` возвращает ошибку чтения.
+    struct Chunks(Vec<Option<&'static [u8]>>);
+
+    impl io::Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+
+            return match self.0.remove(0) {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                None => Err(io::Error::other("сбой чтения")),
+            };
+        }
+    }
+
+    /// Источник с ошибкой чтения на второй строке.
+    fn failing_reader() -> BufReader<Chunks> {
+        return BufReader::new(Chunks(vec![
+            Some("Eins -- Один\n".as_bytes()),
+            None,
+            Some("Drei -- Три\n".as_bytes()),
+        ]));
+    }
+
+    #[test]
+    fn io_error_aborts_parsing() {
+        assert!(matches!(
+            parse_reader(failing_reader(), &ParseOptions::default()),
+            Err(ParseError::Io(_))
+        ));
+    }
+
+    /// Источник, каждое чтение из которого заканчивается ошибкой.
+    struct Broken;
+
+    impl io::Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            return Err(io::Error::other("сбой чтения"));
+        }
+    }
+
+    #[test]
+    fn always_failing_reader_is_not_polled_forever() {
+        for options in [
+            ParseOptions::default(),
+            ParseOptions::builder().auto_detect_separator(true).build(),
+        ] {
+            let result = parse_reader(BufReader::new(Broken), &options);
+
+            assert!(matches!(result, Err(ParseError::Io(_))));
+        }
+    }
+
+    #[test]
+    fn read_error_aborts_with_fail_on_read_error() {
+        let options = ParseOptions::builder().fail_on_read_error(true).build();
+
+        assert!(matches!(
+            parse_reader(failing_reader(), &options),
+            Err(ParseError::Io(_))
+        ));
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    #[test]
+    fn invalid_utf8_line_is_reported() {
+        let input: &[u8] = b"Eins -- \xd0\n\xff\xfe -- x\nDrei -- \xd0\xa2\xd1\x80\xd0\xb8\n";
+        let response = parse_reader(input, &ParseOptions::default()).unwrap();
+        let lines: Vec<u32> = response.warnings.iter().map(|x| x.location.line).collect();
+
+        assert_eq!(lines, vec![1, 2]);
+        assert_eq!(pairs(&response), vec![("Drei", "Три")]);
+    }
+
+    #[test]
+    fn defined_variable_is_substituted() {
+        let response = parse_text("@define PRODUCT=Acme Pro\nÜber ${PRODUCT} -- О ${PRODUCT}\n");
+
+        assert_eq!(pairs(&response), vec![("Über Acme Pro", "О Acme Pro")]);
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn undefined_variable_is_kept_and_optionally_flagged() {
+        let input = "Über ${NAME} -- О ${NAME}\n";
+
+        let response = parse_text(input);
+
+        assert_eq!(pairs(&response), vec![("Über ${NAME}", "О ${NAME}")]);
+        assert!(response.warnings.is_empty());
+
+        let options = ParseOptions::builder()
+            .warn_undefined_variables(true)
+            .build();
+        let response = parse_with(input, options);
+
+        assert_eq!(pairs(&response), vec![("Über ${NAME}", "О ${NAME}")]);
+        assert_eq!(response.warnings[0].kind, WarningKind::UndefinedVariable);
+        assert_eq!(response.warnings[0].location.line, 1);
+    }
+
+    #[test]
+    fn escaped_variable_is_not_substituted() {
+        let response = parse_text("@define X=1\n\\${X} und ${X} -- \\${X}\n");
+
+        assert_eq!(pairs(&response), vec![("${X} und 1", "${X}")]);
+    }
+
+    #[test]
+    fn from_str_reports_parse_errors() {
+        assert!(matches!(
+            "@sep #\na # b".parse::<Response>(),
+            Err(ParseError::InvalidSeparator(_))
+        ));
+    }
 }
@@ -0,0 +1,30 @@
+use crate::parser_v2::{parse_str, ParseOptions};
+
+use wasm_bindgen::prelude::*;
+
+/// Парсит текст файла в браузере и возвращает результат в виде строки JSON.
+///
+/// Языки берутся из директивы `@lang` в начале файла. При ошибке парсинга
+/// в JavaScript выбрасывается исключение `Error` с ее описанием.
+/// Модуль WebAssembly собирается командой `cargo rustc --release --lib --features wasm
+/// --target wasm32-unknown-unknown --crate-type cdylib`.
+#[wasm_bindgen]
+pub fn parse_wasm(input: &str) -> Result<String, JsError> {
+    let response = parse_str(input, &ParseOptions::default())?;
+
+    return Ok(serde_json::to_string(&response)?);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_wasm_returns_json() {
+        let json = parse_wasm("@lang de ru\n#a\nHallo -- Привет\n").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["languages"]["original"], "de");
+        assert_eq!(value["fields"][0]["content"][0]["translate"], "Привет");
+    }
+}
@@ -0,0 +1,32 @@
+#![allow(clippy::needless_return)]
+
+use file_parser::parser_v2::{parse_str, ParseError, ParseOptions};
+
+/// Сообщает о виде ошибки так, как это сделал бы внешний код:
+/// `ParseError` помечен `#[non_exhaustive]`, поэтому без ветки `_` код не соберется.
+fn describe(error: &ParseError) -> &'static str {
+    return match error {
+        ParseError::LimitExceeded { .. } => "limit",
+        ParseError::InvalidContent { .. } => "content",
+        _ => "other",
+    };
+}
+
+#[test]
+fn options_are_built_only_through_builder() {
+    let options = ParseOptions::builder().separator("=").max_lines(2).build();
+
+    let response = parse_str("Eins = Один\n", &options).unwrap();
+    assert_eq!(response.fields()[0].content()[0].translate, "Один");
+
+    let error = parse_str("a = b\nc = d\ne = f\n", &options).err().unwrap();
+    assert_eq!(describe(&error), "limit");
+
+    let options = ParseOptions::builder().fail_fast(true).build();
+    let error = parse_str("Eins <b> -- Один\n", &options).err().unwrap();
+    assert_eq!(describe(&error), "content");
+
+    let options = ParseOptions::builder().separator("#").build();
+    let error = parse_str("a # b\n", &options).err().unwrap();
+    assert_eq!(describe(&error), "other");
+}
@@ -0,0 +1,291 @@
+#![allow(clippy::needless_return)]
+
+use serde_json::Value;
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+/// Запускает собранный бинарный файл в каталоге `dir` с аргументами `args`
+/// и передает `stdin` на стандартный ввод.
+fn run(dir: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_file-parser"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    return child.wait_with_output().unwrap();
+}
+
+#[test]
+fn parses_file_into_json_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("foo.txt"), "#greeting\nПривет -- Hallo\n").unwrap();
+
+    let output = run(
+        dir.path(),
+        &[
+            "--input",
+            "foo.txt",
+            "--output",
+            "foo.json",
+            "--original",
+            "ru",
+            "--translate",
+            "de",
+        ],
+        "",
+    );
+
+    assert!(output.status.success());
+
+    let json: Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("foo.json")).unwrap()).unwrap();
+
+    assert_eq!(json["languages"]["original"], "ru");
+    assert_eq!(json["languages"]["translate"], "de");
+    assert_eq!(json["fields"][0]["tags"][0], "greeting");
+    assert_eq!(json["fields"][0]["content"][0]["original"], "Привет");
+    assert_eq!(json["fields"][0]["content"][0]["translate"], "Hallo");
+}
+
+#[test]
+fn reads_stdin_and_writes_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(
+        dir.path(),
+        &["--input", "-", "--output", "-"],
+        "Hallo -- Привет\n",
+    );
+
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["languages"]["original"], "DE");
+    assert_eq!(json["languages"]["translate"], "RU");
+    assert_eq!(json["fields"][0]["content"][0]["translate"], "Привет");
+}
+
+#[test]
+fn rejects_unknown_argument() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(dir.path(), &["--unknown"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--unknown"));
+}
+
+#[test]
+fn check_exits_with_error_on_forbidden_chars() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(
+        dir.path(),
+        &["--check", "--input", "-", "--output", "-"],
+        "Eins -- Один\nZwei <b> -- Два\n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["is_ok"], false);
+    assert_eq!(json["errors"][0]["string"], "Zwei <b> -- Два");
+}
+
+#[test]
+fn check_succeeds_on_clean_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(
+        dir.path(),
+        &["--check", "--input", "-", "--output", "-"],
+        "Eins -- Один\n",
+    );
+
+    assert!(output.status.success());
+}
+
+/// Убирает из JSON номера строк, которые меняются при повторной записи текста.
+fn without_lines(mut value: Value) -> Value {
+    match &mut value {
+        Value::Object(map) => {
+            map.remove("line");
+            for item in map.values_mut() {
+                *item = without_lines(item.take());
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                *item = without_lines(item.take());
+            }
+        }
+        _ => {}
+    }
+
+    return value;
+}
+
+#[test]
+fn reverse_output_parses_back_to_same_response() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("source.txt"),
+        "@lang de ru\n// Kopf\n\n#a\nEins -- Один\nx -- has -- dash\n\"\"\"quoted\"\"\" -- x\n\"@comment hi\" -- y\nz -- \"\"\"q\"\"\"\n##a\n@lang en ru\n#b\nTwo -- Два\n",
+    )
+    .unwrap();
+
+    let steps: [&[&str]; 3] = [
+        &["--input", "source.txt", "--output", "first.json"],
+        &[
+            "--reverse",
+            "--input",
+            "first.json",
+            "--output",
+            "again.txt",
+        ],
+        &["--input", "again.txt", "--output", "second.json"],
+    ];
+
+    for args in steps {
+        let output = run(dir.path(), args, "");
+        assert!(output.status.success(), "{args:?}: {output:?}");
+    }
+
+    let read = |name: &str| -> Value {
+        let text = fs::read_to_string(dir.path().join(name)).unwrap();
+        return without_lines(serde_json::from_str(&text).unwrap());
+    };
+    let first = read("first.json");
+
+    assert_eq!(first["fields"][0]["content"][1]["translate"], "has -- dash");
+    assert_eq!(first["fields"][0]["content"][2]["original"], "\"quoted\"");
+    assert_eq!(first["fields"][0]["content"][3]["original"], "@comment hi");
+    assert_eq!(first["fields"][0]["content"][4]["translate"], "\"q\"");
+    assert_eq!(first["fields"][1]["languages"]["original"], "en");
+    assert_eq!(read("second.json"), first);
+}
+
+#[test]
+fn stats_prints_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(
+        dir.path(),
+        &["--stats"],
+        "#a\nEins -- Один\nZwei --\n##a\n#b\n@tags a\nDrei -- Три\nBöse <x> -- Плохо\n",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Полей:             2
+Текстов:           3
+Без перевода:      1
+Строк с ошибками:  1
+Частые тэги:
+  a  3
+  b  1
+"
+    );
+}
+
+#[test]
+fn config_file_is_overridden_by_flags_and_directives() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fileparser.json"),
+        r#"{ "default_separator": "=", "original_language": "en", "translate_language": "fr" }"#,
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["--original", "it"], "Eins = Один\n");
+
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["separator"], "=");
+    assert_eq!(json["languages"]["original"], "it");
+    assert_eq!(json["languages"]["translate"], "fr");
+
+    let output = run(
+        dir.path(),
+        &["--original", "it"],
+        "@lang de ru\nEins = Один\n",
+    );
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["languages"]["original"], "de");
+}
+
+/// Возвращает отсортированные имена файлов в каталоге.
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    return names;
+}
+
+#[test]
+fn split_by_tag_writes_file_per_tag() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(
+        dir.path(),
+        &["--split-by-tag", "out"],
+        "Null -- Ноль\n#button\nOK -- ОК\n#menu\nDatei -- Файл\n##button\nHilfe -- Справка\n##menu\n#a b\nX -- Х\n",
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        file_names(&dir.path().join("out")),
+        vec!["a_b.json", "button.json", "menu.json"]
+    );
+
+    let read = |name: &str| -> Value {
+        let text = fs::read_to_string(dir.path().join("out").join(name)).unwrap();
+        return serde_json::from_str(&text).unwrap();
+    };
+    let originals = |value: &Value| -> Vec<String> {
+        return value["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|field| field["content"].as_array().unwrap().iter())
+            .map(|text| text["original"].as_str().unwrap().to_string())
+            .collect();
+    };
+
+    assert_eq!(originals(&read("button.json")), vec!["OK", "Datei"]);
+    assert_eq!(originals(&read("menu.json")), vec!["Datei", "Hilfe"]);
+    assert_eq!(read("a_b.json")["all_tags"], serde_json::json!(["a b"]));
+}
+
+#[test]
+fn split_by_tag_rejects_colliding_file_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(
+        dir.path(),
+        &["--split-by-tag", "out"],
+        "#Menu\nEins -- Один\n##Menu\n#menu\nZwei -- Два\n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`menu.json`"));
+    assert!(!dir.path().join("out").exists());
+}
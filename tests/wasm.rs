@@ -0,0 +1,18 @@
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+#![allow(clippy::needless_return)]
+
+use file_parser::wasm::parse_wasm;
+
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn parses_sample_string() {
+    let json = parse_wasm("@lang de ru\nHallo -- Привет\n").unwrap();
+
+    assert!(json.contains("\"translate\":\"Привет\""));
+}
+
+#[wasm_bindgen_test]
+fn reports_parse_error() {
+    assert!(parse_wasm("@sep \n").is_err());
+}